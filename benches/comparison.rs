@@ -0,0 +1,220 @@
+//! Compares SyncCow to `std::sync::RwLock` and `arc_swap::ArcSwap`.
+//!
+//! Run with `cargo bench --bench comparison`, or e.g. `cargo bench --bench comparison -- read`
+//! to only run the read benchmarks.
+//!
+//! For each container, number of concurrent readers (1, 4, 16, 64) and value size (a `u64`, a
+//! 1KB array, a 1MB `Vec`), it measures:
+//! - `read`: the latency of one read while the other readers read in a loop. SyncCow's `read`
+//!   clones the Arc, `RwLock` acquires a read guard and `ArcSwap` loads a guard.
+//! - `write`: the latency of publishing a clone of the value while all readers read in a loop.
+//!
+//! Before that, the heap memory each container allocates for its value is printed. SyncCow
+//! stores its value once per buffer, so it needs twice the memory of the others.
+//!
+//! The measurement settings are fixed, so runs on the same machine are comparable. Readers of
+//! all containers contend with each other only on multi-core machines.
+//!
+//! With many readers reading in a loop, and more readers than cores, SyncCow's writer often
+//! finds a reader that was preempted while announced in the buffer the writer wants to publish
+//! into, and has to wait until the scheduler resumes that reader. Compare results from a
+//! machine with at least as many cores as readers to judge read contention.
+
+use arc_swap::ArcSwap;
+use criterion::{criterion_group, BenchmarkId, Criterion};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering::Relaxed};
+use std::sync::{Arc, RwLock};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use sync_cow::SyncCow;
+
+/// Counts the bytes currently allocated on the heap
+struct CountingAlloc;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const READERS: [usize; 4] = [1, 4, 16, 64];
+
+/// A container shared between readers and a writer
+trait Shared<V>: Send + Sync + 'static {
+    const NAME: &'static str;
+    fn new(value: V) -> Self;
+    fn read(&self);
+    fn write(&self, value: &V);
+}
+
+impl<V: Clone + Send + Sync + 'static> Shared<V> for SyncCow<V> {
+    const NAME: &'static str = "SyncCow";
+
+    fn new(value: V) -> Self {
+        SyncCow::new(value)
+    }
+
+    fn read(&self) {
+        black_box(SyncCow::read(self));
+    }
+
+    fn write(&self, value: &V) {
+        self.set(value.clone());
+    }
+}
+
+impl<V: Clone + Send + Sync + 'static> Shared<V> for RwLock<V> {
+    const NAME: &'static str = "RwLock";
+
+    fn new(value: V) -> Self {
+        RwLock::new(value)
+    }
+
+    fn read(&self) {
+        black_box(&*RwLock::read(self).unwrap());
+    }
+
+    fn write(&self, value: &V) {
+        let value = value.clone();
+        *RwLock::write(self).unwrap() = value;
+    }
+}
+
+impl<V: Clone + Send + Sync + 'static> Shared<V> for ArcSwap<V> {
+    const NAME: &'static str = "ArcSwap";
+
+    fn new(value: V) -> Self {
+        ArcSwap::from_pointee(value)
+    }
+
+    fn read(&self) {
+        black_box(&**self.load());
+    }
+
+    fn write(&self, value: &V) {
+        self.store(Arc::new(value.clone()));
+    }
+}
+
+/// Readers reading `shared` in a loop until dropped
+struct BackgroundReaders {
+    stopped: Arc<AtomicBool>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl BackgroundReaders {
+    fn spawn<S: Shared<V>, V>(shared: &Arc<S>, readers: usize) -> BackgroundReaders {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let handles = (0..readers)
+            .map(|_| {
+                let shared = shared.clone();
+                let stopped = stopped.clone();
+                std::thread::spawn(move || {
+                    while !stopped.load(Relaxed) {
+                        shared.read();
+                    }
+                })
+            })
+            .collect();
+        BackgroundReaders { stopped, handles }
+    }
+}
+
+impl Drop for BackgroundReaders {
+    fn drop(&mut self) {
+        self.stopped.store(true, Relaxed);
+        for handle in self.handles.drain(..) {
+            handle.join().unwrap();
+        }
+    }
+}
+
+/// Heap memory of a boxed container holding a freshly built value, including the value's own
+/// allocations, e.g. a Vec's buffer
+fn footprint<S, V>(make_value: fn() -> V, new: fn(V) -> S) -> usize {
+    let before = ALLOCATED.load(Relaxed);
+    let shared = Box::new(new(make_value()));
+    let footprint = ALLOCATED.load(Relaxed) - before;
+    drop(shared);
+    footprint
+}
+
+fn print_footprints<V: Clone + Send + Sync + 'static>(size: &str, make_value: fn() -> V) {
+    println!(
+        "{:>3}: value {:>7}B, SyncCow {:>7}B, RwLock {:>7}B, ArcSwap {:>7}B",
+        size,
+        footprint(make_value, |value| value),
+        footprint(make_value, <SyncCow<V> as Shared<V>>::new),
+        footprint(make_value, <RwLock<V> as Shared<V>>::new),
+        footprint(make_value, <ArcSwap<V> as Shared<V>>::new),
+    );
+}
+
+fn bench_container<S: Shared<V>, V: Clone>(c: &mut Criterion, size: &str, value: V) {
+    let mut read = c.benchmark_group(format!("read/{}", size));
+    for readers in READERS {
+        let shared = Arc::new(S::new(value.clone()));
+        // The measuring thread is one of the readers
+        let _background = BackgroundReaders::spawn(&shared, readers - 1);
+        read.bench_function(BenchmarkId::new(S::NAME, readers), |b| b.iter(|| shared.read()));
+    }
+    read.finish();
+
+    let mut write = c.benchmark_group(format!("write/{}", size));
+    for readers in READERS {
+        let shared = Arc::new(S::new(value.clone()));
+        let _background = BackgroundReaders::spawn(&shared, readers);
+        write.bench_function(BenchmarkId::new(S::NAME, readers), |b| {
+            b.iter(|| shared.write(&value))
+        });
+    }
+    write.finish();
+}
+
+fn bench_size<V: Clone + Send + Sync + 'static>(c: &mut Criterion, size: &str, value: V) {
+    bench_container::<SyncCow<V>, V>(c, size, value.clone());
+    bench_container::<RwLock<V>, V>(c, size, value.clone());
+    bench_container::<ArcSwap<V>, V>(c, size, value);
+}
+
+fn benches(c: &mut Criterion) {
+    bench_size(c, "u64", 0u64);
+    bench_size(c, "1KB", [0u8; 1024]);
+    bench_size(c, "1MB", vec![0u8; 1 << 20]);
+}
+
+fn config() -> Criterion {
+    Criterion::default()
+        .warm_up_time(Duration::from_millis(500))
+        .measurement_time(Duration::from_secs(2))
+        .sample_size(50)
+}
+
+criterion_group! {
+    name = comparison;
+    config = config();
+    targets = benches
+}
+
+fn main() {
+    println!("Heap memory footprint per container:");
+    print_footprints("u64", || 0u64);
+    print_footprints("1KB", || [0u8; 1024]);
+    print_footprints("1MB", || vec![0u8; 1 << 20]);
+
+    comparison();
+    Criterion::default().configure_from_args().final_summary();
+}
@@ -0,0 +1,84 @@
+//! Measures the read throughput of `read`, `peek` and `borrow` for different numbers of
+//! concurrent reader threads.
+//!
+//! Run with `cargo bench --bench read_throughput`.
+//!
+//! Readers are counted in per-thread stripes, one stripe per available core, so concurrent
+//! readers rarely contend for a single shared counter. To compare against a single shared
+//! counter, run the bench again with striping disabled:
+//! ```text
+//! RUSTFLAGS="--cfg sync_cow_unstriped" cargo bench --bench read_throughput
+//! ```
+//! `read` also increments and decrements the strong count of the shared `Arc`, which contends
+//! like an unstriped counter and hides most of the gain. `peek` and `borrow` only touch the
+//! reader counts, so they show the effect of striping. On a single-core machine only one stripe
+//! is used and readers don't run concurrently, so both runs measure the same; the gain only
+//! shows up with multiple cores.
+
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use std::sync::{Arc, Barrier};
+use std::time::{Duration, Instant};
+use sync_cow::SyncCow;
+
+const DURATION: Duration = Duration::from_millis(500);
+
+type Cow = SyncCow<Vec<u64>>;
+/// One read of the measured kind
+type Read = fn(&Cow);
+
+fn reads_per_sec(readers: usize, read: Read) -> f64 {
+    let cow = Arc::new(SyncCow::new(vec![0u64; 64]));
+    let stopped = Arc::new(AtomicBool::new(false));
+    let barrier = Arc::new(Barrier::new(readers + 1));
+    let handles: Vec<_> = (0..readers)
+        .map(|_| {
+            let cow = cow.clone();
+            let stopped = stopped.clone();
+            let barrier = barrier.clone();
+            std::thread::spawn(move || {
+                barrier.wait();
+                let mut reads = 0u64;
+                while !stopped.load(Relaxed) {
+                    read(&cow);
+                    reads += 1;
+                }
+                reads
+            })
+        })
+        .collect();
+
+    barrier.wait();
+    let start = Instant::now();
+    std::thread::sleep(DURATION);
+    stopped.store(true, Relaxed);
+    let reads: u64 = handles.into_iter().map(|h| h.join().unwrap()).sum();
+    reads as f64 / start.elapsed().as_secs_f64()
+}
+
+fn main() {
+    let striping = if cfg!(sync_cow_unstriped) { "unstriped" } else { "striped" };
+    let cores = std::thread::available_parallelism().map_or(1, |cores| cores.get());
+    println!("reader counts {}, {} cores", striping, cores);
+
+    let reads: [(&str, Read); 3] = [
+        ("read", |cow| {
+            std::hint::black_box(cow.read());
+        }),
+        ("peek", |cow| {
+            cow.peek(|v| std::hint::black_box(v[0]));
+        }),
+        ("borrow", |cow| {
+            std::hint::black_box(cow.borrow()[0]);
+        }),
+    ];
+    for (name, read) in reads {
+        for readers in [1, 8, 64] {
+            println!(
+                "{:>6}, readers: {:>2}, reads/s: {:.3e}",
+                name,
+                readers,
+                reads_per_sec(readers, read)
+            );
+        }
+    }
+}
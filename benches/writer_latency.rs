@@ -0,0 +1,119 @@
+//! Measures the latency of `edit` under heavy concurrent reading for different buffer counts,
+//! and the writer's CPU time for different backoff strategies when readers are slow.
+//!
+//! Run with `cargo bench --bench writer_latency`.
+//!
+//! Sample output on a single-core machine. With two buffers, the writer often has to publish
+//! into a buffer a preempted reader is still announced in, and waits until the scheduler resumes
+//! that reader. Additional buffers avoid that. With slow readers, spinning burns the writer's
+//! time slices, while yielding and the exponential backoff leave the CPU to the readers. The
+//! writer's CPU time is only updated by the scheduler, so very short runs show 0ns:
+//! ```text
+//! 16 readers, 500 edits
+//! buffers: 2, backoff: Exponential, mean: 64.727427ms, p99: 80.003875ms, max: 115.92222ms, writer CPU: 9.503547ms
+//! buffers: 4, backoff: Exponential, mean: 148ns, p99: 341ns, max: 977ns, writer CPU: 0ns
+//! 16 readers holding the value for 20µs, 200 edits
+//! buffers: 2, backoff: Yield, mean: 66.259674ms, p99: 84.000889ms, max: 91.991993ms, writer CPU: 2.901834ms
+//! buffers: 2, backoff: Spin, mean: 72.259511ms, p99: 131.993304ms, max: 132.007469ms, writer CPU: 839.360143ms
+//! buffers: 2, backoff: Exponential, mean: 66.09973ms, p99: 84.010432ms, max: 95.996687ms, writer CPU: 3.283499ms
+//! ```
+
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use std::sync::{Arc, Barrier};
+use std::time::{Duration, Instant};
+use sync_cow::{Backoff, SyncCow};
+
+const READERS: usize = 16;
+const EDITS: usize = 500;
+/// Time slow readers hold the value, i.e. keep the writer from publishing into its buffer
+const SLOW_READ: Duration = Duration::from_micros(20);
+const SLOW_EDITS: usize = 200;
+
+/// CPU time the calling thread has spent running so far, from Linux' schedstat
+fn thread_cpu_time() -> Option<Duration> {
+    let schedstat = std::fs::read_to_string("/proc/thread-self/schedstat").ok()?;
+    let nanos = schedstat.split_whitespace().next()?.parse().ok()?;
+    Some(Duration::from_nanos(nanos))
+}
+
+/// Sorted latencies of all edits, and the CPU time the writer spent on them
+fn writer_latencies(
+    buffers: usize,
+    backoff: Backoff,
+    read_time: Duration,
+    edits: usize,
+) -> (Vec<Duration>, Option<Duration>) {
+    let cow = SyncCow::builder().buffers(buffers).backoff(backoff);
+    let cow = Arc::new(cow.build(vec![0u64; 64]));
+    let stopped = Arc::new(AtomicBool::new(false));
+    let barrier = Arc::new(Barrier::new(READERS + 1));
+    let readers: Vec<_> = (0..READERS)
+        .map(|_| {
+            let cow = cow.clone();
+            let stopped = stopped.clone();
+            let barrier = barrier.clone();
+            std::thread::spawn(move || {
+                barrier.wait();
+                let mut sum = 0;
+                while !stopped.load(Relaxed) {
+                    if read_time.is_zero() {
+                        sum += cow.read()[0];
+                    } else {
+                        let start = Instant::now();
+                        cow.peek(|v| {
+                            while start.elapsed() < read_time {
+                                sum += std::hint::black_box(v[0]);
+                            }
+                        });
+                    }
+                }
+                sum
+            })
+        })
+        .collect();
+
+    barrier.wait();
+    let cpu_start = thread_cpu_time();
+    let mut latencies: Vec<_> = (0..edits)
+        .map(|_| {
+            let start = Instant::now();
+            cow.edit(|v| v[0] += 1);
+            start.elapsed()
+        })
+        .collect();
+    let cpu_time = thread_cpu_time().zip(cpu_start).map(|(end, start)| end - start);
+
+    stopped.store(true, Relaxed);
+    for reader in readers {
+        reader.join().unwrap();
+    }
+    latencies.sort();
+    (latencies, cpu_time)
+}
+
+fn report(buffers: usize, backoff: Backoff, read_time: Duration, edits: usize) {
+    let (latencies, cpu_time) = writer_latencies(buffers, backoff, read_time, edits);
+    let mean = latencies.iter().sum::<Duration>() / latencies.len() as u32;
+    let p99 = latencies[latencies.len() * 99 / 100];
+    let max = latencies[latencies.len() - 1];
+    let cpu_time = cpu_time.map_or(String::from("n/a"), |cpu| format!("{:?}", cpu));
+    println!(
+        "buffers: {}, backoff: {:?}, mean: {:?}, p99: {:?}, max: {:?}, writer CPU: {}",
+        buffers, backoff, mean, p99, max, cpu_time
+    );
+}
+
+fn main() {
+    println!("{} readers, {} edits", READERS, EDITS);
+    for buffers in [2, 4] {
+        report(buffers, Backoff::default(), Duration::ZERO, EDITS);
+    }
+
+    println!(
+        "{} readers holding the value for {:?}, {} edits",
+        READERS, SLOW_READ, SLOW_EDITS
+    );
+    for backoff in [Backoff::Yield, Backoff::Spin, Backoff::Exponential] {
+        report(2, backoff, SLOW_READ, SLOW_EDITS);
+    }
+}
@@ -0,0 +1,42 @@
+//! Reading several related fields from one consistent snapshot.
+//!
+//! Each read of a SyncCow returns the latest value at that moment, so reading it once per field
+//! may mix fields of different versions while a writer edits it concurrently. Reading all fields
+//! from a single snapshot guarantees they belong to the same version.
+
+use std::sync::Arc;
+use sync_cow::SyncCow;
+
+#[derive(Clone, Debug)]
+struct Position {
+    x: i64,
+    y: i64,
+    step: u64,
+}
+
+fn main() {
+    let position = Arc::new(SyncCow::new(Position { x: 0, y: 0, step: 0 }));
+
+    // The writer moves diagonally, so x and y are always equal to the step count
+    let writer = {
+        let position = position.clone();
+        std::thread::spawn(move || {
+            for _ in 0..1000 {
+                position.edit(|p| {
+                    p.x += 1;
+                    p.y += 1;
+                    p.step += 1;
+                });
+            }
+        })
+    };
+
+    for _ in 0..1000 {
+        let snapshot = position.snapshot();
+        let (x, y, step) = (snapshot.x, snapshot.y, snapshot.step);
+        assert!(x == y && y as u64 == step, "Inconsistent snapshot {:?}", *snapshot);
+    }
+
+    writer.join().unwrap();
+    println!("Final position {:?}", *position.snapshot());
+}
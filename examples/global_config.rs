@@ -0,0 +1,40 @@
+//! A process-wide configuration stored in a static SyncCow.
+//!
+//! `SyncCow::new` allocates the buffers and the Arcs holding the value, so it can't be a
+//! `const fn` and a SyncCow can't be put into a `static` directly. `LazyLock` creates it on
+//! first access instead, after which all threads share it without further synchronization.
+
+use std::sync::LazyLock;
+use sync_cow::SyncCow;
+
+#[derive(Clone, Debug)]
+struct Config {
+    verbose: bool,
+    workers: usize,
+}
+
+static CONFIG: LazyLock<SyncCow<Config>> = LazyLock::new(|| {
+    SyncCow::new(Config {
+        verbose: false,
+        workers: 4,
+    })
+});
+
+fn main() {
+    let workers: Vec<_> = (0..CONFIG.read().workers)
+        .map(|id| {
+            std::thread::spawn(move || {
+                let config = CONFIG.read();
+                println!("Worker {} started with {:?}", id, *config);
+            })
+        })
+        .collect();
+
+    CONFIG.edit(|config| config.verbose = true);
+    for worker in workers {
+        worker.join().unwrap();
+    }
+
+    assert!(CONFIG.read().verbose);
+    println!("Config is now {:?}", *CONFIG.read());
+}
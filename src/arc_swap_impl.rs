@@ -0,0 +1,36 @@
+//! Conversions between SyncCow and `arc_swap::ArcSwap`
+
+use crate::SyncCow;
+use arc_swap::ArcSwap;
+
+impl<T> SyncCow<T> {
+    /// Consume the SyncCow and move the current value into an `ArcSwap`, without cloning it.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(vec![1, 2]);
+    /// let swap = cow.to_arc_swap();
+    /// assert_eq!(**swap.load(), [1, 2]);
+    /// ```
+    pub fn to_arc_swap(mut self) -> ArcSwap<T> {
+        ArcSwap::new(self.get_arc_mut().clone())
+    }
+
+    /// Create a new SyncCow publishing the value currently stored in `swap`, without cloning it.
+    ///
+    /// Like [`from_arc`](SyncCow::from_arc), the value is shared with `swap` until the first
+    /// publish of either side.
+    ///
+    /// ```
+    /// let swap = arc_swap::ArcSwap::from_pointee(vec![1, 2]);
+    /// let cow = sync_cow::SyncCow::from_arc_swap(&swap);
+    /// assert_eq!(*cow.read(), [1, 2]);
+    /// ```
+    pub fn from_arc_swap(swap: &ArcSwap<T>) -> SyncCow<T>
+    where
+        T: Clone,
+    {
+        let mut cow = SyncCow::from_arc(swap.load_full());
+        cow.cloner = crate::clone_cloner();
+        cow
+    }
+}
@@ -0,0 +1,34 @@
+//! Editing from async code, see [`SyncCow::edit_async`](crate::SyncCow::edit_async)
+
+use crate::SyncCow;
+
+impl<T> SyncCow<T> {
+    /// Edit the contents of the SyncCow from async code. Awaits the write-lock.
+    ///
+    /// Like [`edit`](SyncCow::edit), but instead of blocking the current thread while another
+    /// writer holds the write-lock, the returned future waits for it. Async writers are queued
+    /// behind a `tokio::sync::Mutex`, while writers using the blocking methods are waited for
+    /// without polling: the future is woken when they release the write-lock. Once the
+    /// write-lock is acquired, `edit_fn` runs synchronously on the current task, so it should
+    /// not take long.
+    ///
+    /// As tasks share threads, reentrant edits aren't detected: calling `edit_async` while the
+    /// same task holds an [`edit_guard`](SyncCow::edit_guard) waits forever.
+    ///
+    /// ```
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let cow = sync_cow::SyncCow::new(5);
+    /// cow.edit_async(|x| *x = 6).await;
+    /// assert_eq!(*cow.read(), 6);
+    /// # }
+    /// ```
+    pub async fn edit_async<F, R>(&self, edit_fn: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let _async_lck = self.async_write_lock.lock().await;
+        let _lck = self.write_lock.lock_async().await;
+        self.edit_locked(edit_fn)
+    }
+}
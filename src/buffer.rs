@@ -0,0 +1,224 @@
+//! Buffers holding published values, and the striped reader counts guarding them
+
+use crate::instrument;
+use crate::Backoff;
+use crate::sync::{self, fence, AtomicPtr, AtomicU32};
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+#[cfg(feature = "allocator_api")]
+use core::alloc::Allocator;
+use core::ops::Deref;
+use core::sync::atomic::Ordering::{Acquire, Relaxed, Release, SeqCst};
+
+/// Counter of readers, aligned to its own cache line so stripes don't share one
+///
+/// A count grows with every reader held by a thread of its stripe. Reads and `peek` only hold
+/// one for their duration, but the [`ReadRef`](crate::ReadRef) returned by `borrow` may be held
+/// for arbitrarily long, or leaked with `mem::forget`, which never decrements the count. As
+/// leaking a guard costs no memory, nothing bounds the count, so [`Buffer::enter`] panics once it
+/// exceeds [`MAX_READERS`] instead of letting it wrap around to zero, which would let a writer
+/// free a value still being read. Like `Arc`'s reference count, the limit is far beyond what
+/// working code reaches, so a `u32` suffices even on 64-bit targets.
+///
+/// Leaked readers are supported: they only keep writers from publishing into their buffer, and
+/// dropping the SyncCow ignores their counts. Every increment in [`Buffer::enter`] is matched by
+/// at most one decrement when the [`Reader`] is dropped, also when unwinding from a panicking
+/// clone or `peek` closure, so the count can't underflow. Debug builds assert that.
+#[repr(align(64))]
+struct ReaderCount(AtomicU32);
+
+/// Readers a stripe may count. Leaves half of the `u32` range as headroom for threads
+/// incrementing concurrently before they see the limit.
+const MAX_READERS: u32 = u32::MAX / 2;
+
+/// A published value and the number of readers currently accessing it
+///
+/// Instead of a single shared counter, readers are counted in several stripes, and each thread
+/// always uses the same stripe, picked by its index. Readers on different threads then mostly
+/// don't contend for the same cache line. The writer has to check all stripes when draining the
+/// buffer. Each stripe takes a cache line, so with up to 16 stripes the counts cost up to
+/// 16 × 64 B = 1 KiB per buffer.
+///
+/// Hazard pointers would avoid the shared counters entirely, but need a slot registered per
+/// thread, which requires thread-locals and thus `std`, and defer freeing replaced values to a
+/// retire list instead of the bounded drain. With striping, threads whose indices fall into
+/// different stripes already touch separate cache lines, so the counts are kept.
+pub(crate) struct Buffer<T> {
+    pub(crate) ptr: AtomicPtr<Arc<T>>,
+    readers: Box<[ReaderCount]>,
+    alloc: BoxAlloc,
+}
+
+/// Allocator of the Boxes holding published Arcs
+///
+/// With the `allocator_api` feature, this is the allocator passed to
+/// [`SyncCow::new_in`](crate::SyncCow::new_in), shared by all buffers of the SyncCow, or the
+/// global allocator. Without it, the Boxes always use the global allocator.
+#[derive(Clone)]
+pub(crate) struct BoxAlloc {
+    #[cfg(feature = "allocator_api")]
+    alloc: Arc<dyn Allocator + Send + Sync>,
+}
+
+impl BoxAlloc {
+    pub(crate) fn global() -> BoxAlloc {
+        BoxAlloc {
+            #[cfg(feature = "allocator_api")]
+            alloc: Arc::new(alloc::alloc::Global),
+        }
+    }
+
+    #[cfg(feature = "allocator_api")]
+    pub(crate) fn new(alloc: impl Allocator + Send + Sync + 'static) -> BoxAlloc {
+        BoxAlloc {
+            alloc: Arc::new(alloc),
+        }
+    }
+
+    /// Box `arc` and leak it, so it is only freed by [`release`](BoxAlloc::release).
+    ///
+    /// All Boxes holding published Arcs are allocated here and freed in `release`, so the
+    /// allocation strategy is confined to these two functions.
+    fn allocate<T>(&self, arc: Arc<T>) -> *mut Arc<T> {
+        #[cfg(feature = "allocator_api")]
+        return Box::into_raw_with_allocator(Box::new_in(arc, &*self.alloc)).0;
+        #[cfg(not(feature = "allocator_api"))]
+        Box::into_raw(Box::new(arc))
+    }
+
+    /// Free a Box returned by [`allocate`](BoxAlloc::allocate) of the same allocator.
+    ///
+    /// # Safety
+    ///
+    /// Like [`Buffer::release`], and `ptr` must have been allocated by this allocator.
+    unsafe fn release<T>(&self, ptr: *mut Arc<T>) {
+        #[cfg(feature = "allocator_api")]
+        drop(unsafe { Box::from_raw_in(ptr, &*self.alloc) });
+        #[cfg(not(feature = "allocator_api"))]
+        drop(unsafe { Box::from_raw(ptr) });
+    }
+}
+
+impl<T> Buffer<T> {
+    pub(crate) fn new(arc: Arc<T>, alloc: BoxAlloc) -> Buffer<T> {
+        Buffer {
+            ptr: AtomicPtr::new(alloc.allocate(arc)),
+            readers: (0..sync::reader_stripes())
+                .map(|_| ReaderCount(AtomicU32::new(0)))
+                .collect::<Vec<_>>()
+                .into_boxed_slice(),
+            alloc,
+        }
+    }
+
+    /// The allocator of the buffer's Boxes, to create further buffers with
+    pub(crate) fn alloc(&self) -> BoxAlloc {
+        self.alloc.clone()
+    }
+
+    /// Free a Box returned by [`swap`](Buffer::swap), releasing its Arc.
+    ///
+    /// # Safety
+    ///
+    /// No reader may access `ptr` anymore, i.e. the buffer must have been drained since the swap,
+    /// and `ptr` must not be released twice.
+    pub(crate) unsafe fn release(&self, ptr: *mut Arc<T>) {
+        unsafe { self.alloc.release(ptr) };
+    }
+
+    /// Store `arc` as the buffer's value and return the previous one, to be freed with
+    /// [`release`](Buffer::release) once the buffer is drained.
+    pub(crate) fn swap(&self, arc: Arc<T>) -> *mut Arc<T> {
+        // Release publishes the new Arc's contents to readers loading this pointer
+        self.ptr.swap(self.alloc.allocate(arc), Release)
+    }
+
+    /// Announce a reader of the calling thread and load the buffer's value.
+    ///
+    /// The value stays valid until the returned reader is dropped.
+    pub(crate) fn enter(&self) -> Reader<'_, T> {
+        let stripe = sync::reader_stripe() % self.readers.len();
+        // The increment, the SeqCst fence and the pointer load form the reader half of the
+        // store-load pair with the writer's swap and `drain` (see `SyncCow::publish_locked`).
+        let previous = self.readers[stripe].0.fetch_add(1, SeqCst);
+        if previous >= MAX_READERS {
+            self.readers[stripe].0.fetch_sub(1, Release);
+            panic!("reader count overflow, too many read guards held or leaked");
+        }
+        fence(SeqCst);
+        // Acquire pairs with the writer's Release swap, making the pointee visible
+        let arc = unsafe { &*self.ptr.load(Acquire) };
+        Reader {
+            buffer: self,
+            stripe,
+            arc,
+        }
+    }
+
+    /// Whether any reader is announced. Only a heuristic, as readers may come and go.
+    pub(crate) fn has_readers(&self) -> bool {
+        self.readers.iter().any(|count| count.0.load(Relaxed) != 0)
+    }
+
+    /// Number of announced readers. Only a snapshot, as readers may come and go.
+    pub(crate) fn readers(&self) -> usize {
+        self.readers.iter().map(|count| count.0.load(Relaxed) as usize).sum()
+    }
+
+    /// Bytes allocated by the buffer itself, i.e. the Box holding its Arc and the reader counts.
+    /// The value is not included.
+    pub(crate) fn allocated_size(&self) -> usize {
+        core::mem::size_of::<Arc<T>>() + core::mem::size_of_val(&*self.readers)
+    }
+
+    /// Wait until no reader is announced anymore.
+    ///
+    /// Acquire pairs with the readers' Release decrements, so their accesses happen-before
+    /// anything the writer does afterwards, e.g. freeing the previous value.
+    pub(crate) fn drain(&self, backoff: Backoff) {
+        let mut spins = 0;
+        for count in self.readers.iter() {
+            while count.0.load(Acquire) != 0 {
+                sync::backoff(backoff, spins);
+                spins += 1;
+            }
+        }
+        instrument::drain_spins(spins);
+    }
+}
+
+impl<T> Drop for Buffer<T> {
+    fn drop(&mut self) {
+        // `&mut self` guarantees no reader or writer accesses the pointer anymore: readers borrow
+        // the SyncCow for the whole read, so e.g. scoped threads reading it must have been joined
+        // before its owner can drop it. Waiting for the reader counts like `drain` is therefore
+        // unnecessary. They may still be nonzero from `borrow` guards leaked with `mem::forget`,
+        // which never access the value again.
+        unsafe { self.release(self.ptr.load(Acquire)) };
+    }
+}
+
+/// Announced reader of a buffer, keeping the loaded value valid while it's alive
+pub(crate) struct Reader<'a, T> {
+    buffer: &'a Buffer<T>,
+    stripe: usize,
+    arc: &'a Arc<T>,
+}
+
+impl<T> Deref for Reader<'_, T> {
+    type Target = Arc<T>;
+
+    fn deref(&self) -> &Arc<T> {
+        self.arc
+    }
+}
+
+impl<T> Drop for Reader<'_, T> {
+    fn drop(&mut self) {
+        // Release orders the reader's accesses before the writer's `drain` observes the
+        // decrement. Dropping also releases the reader when unwinding, so writers don't hang.
+        let previous = self.buffer.readers[self.stripe].0.fetch_sub(1, Release);
+        debug_assert!(previous != 0, "reader count underflow");
+    }
+}
@@ -0,0 +1,78 @@
+//! Builder for SyncCows with non-default configuration
+
+use crate::SyncCow;
+use core::marker::PhantomData;
+
+/// How a writer waits for readers to leave the buffer it wants to publish into, and for the
+/// write-lock in [`try_edit_timeout`](SyncCow::try_edit_timeout).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backoff {
+    /// Yield the thread to the scheduler between checks. Without the `std` feature there's no
+    /// scheduler to yield to, so this behaves like [`Backoff::Spin`].
+    Yield,
+    /// Busy-wait with `core::hint::spin_loop` between checks. Reacts faster than yielding when
+    /// readers are short and run on other cores, but burns CPU time while waiting.
+    Spin,
+    /// Spin for the first few checks, then yield, and finally sleep for exponentially growing
+    /// durations of up to a millisecond. Short waits are as fast as spinning, while long waits
+    /// for slow readers barely use any CPU time. Without the `std` feature, the number of spins
+    /// between checks grows exponentially instead.
+    Exponential,
+}
+
+/// Exponential backoff with the `std` feature, spinning otherwise
+impl Default for Backoff {
+    fn default() -> Self {
+        if cfg!(feature = "std") {
+            Backoff::Exponential
+        } else {
+            Backoff::Spin
+        }
+    }
+}
+
+/// Builder for a [`SyncCow`], created by [`SyncCow::builder`].
+///
+/// ```
+/// use sync_cow::{Backoff, SyncCow};
+/// let cow = SyncCow::builder().buffers(3).backoff(Backoff::Spin).build(5);
+/// cow.edit(|x| *x = 6);
+/// assert_eq!(*cow.read(), 6);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct SyncCowBuilder<T> {
+    buffers: usize,
+    backoff: Backoff,
+    value: PhantomData<fn() -> T>,
+}
+
+impl<T: Clone> SyncCowBuilder<T> {
+    pub(crate) fn new() -> SyncCowBuilder<T> {
+        SyncCowBuilder {
+            buffers: 2,
+            backoff: Backoff::default(),
+            value: PhantomData,
+        }
+    }
+
+    /// Set the number of buffers, see [`SyncCow::with_buffers`]. Defaults to 2.
+    pub fn buffers(mut self, buffers: usize) -> SyncCowBuilder<T> {
+        self.buffers = buffers;
+        self
+    }
+
+    /// Set how writers wait for readers. Defaults to [`Backoff::default`].
+    pub fn backoff(mut self, backoff: Backoff) -> SyncCowBuilder<T> {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Create the SyncCow containing `initial`.
+    ///
+    /// Panics if less than 2 buffers were configured.
+    pub fn build(self, initial: T) -> SyncCow<T> {
+        let mut cow = SyncCow::with_buffers(initial, self.buffers);
+        cow.backoff = self.backoff;
+        cow
+    }
+}
@@ -0,0 +1,38 @@
+//! Blocking iterator over published values, see [`SyncCow::changes`](crate::SyncCow::changes)
+
+use crate::SyncCow;
+use alloc::sync::Arc;
+
+/// An iterator yielding each newly published value of a SyncCow.
+///
+/// Created by [`SyncCow::changes`](crate::SyncCow::changes). [`next`](Iterator::next) blocks
+/// until the version has moved past the last one observed, then yields the current value. It
+/// never returns `None`.
+///
+/// Changes are coalesced: if writers publish several values while the consumer is busy, only
+/// the latest of them is yielded, the intermediate ones are missed.
+pub struct ChangeIter<'a, T> {
+    cow: &'a SyncCow<T>,
+    last: u64,
+}
+
+impl<'a, T> ChangeIter<'a, T> {
+    pub(crate) fn new(cow: &'a SyncCow<T>) -> ChangeIter<'a, T> {
+        ChangeIter {
+            cow,
+            last: cow.version(),
+        }
+    }
+}
+
+impl<T> Iterator for ChangeIter<'_, T> {
+    type Item = Arc<T>;
+
+    fn next(&mut self) -> Option<Arc<T>> {
+        let last = self.last;
+        self.cow.notifier.wait_until(|| self.cow.version() != last);
+        let (value, version) = self.cow.read_if_changed(last)?;
+        self.last = version;
+        Some(value)
+    }
+}
@@ -0,0 +1,85 @@
+//! Releasing excess capacity of collection values, see [`SyncCow::compact`]
+
+use crate::SyncCow;
+use core::sync::atomic::Ordering::Acquire;
+use alloc::collections::{BinaryHeap, VecDeque};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Values holding excess capacity that can be released, see [`SyncCow::compact`].
+///
+/// Implemented for `Vec`, `VecDeque`, `BinaryHeap`, `String` and, with the `std` feature,
+/// `HashMap` and `HashSet`.
+pub trait Compactible {
+    /// Shrink the capacity as much as possible
+    fn compact(&mut self);
+}
+
+macro_rules! impl_compactible {
+    ($($ty:ty => [$($param:tt)*]),* $(,)?) => {
+        $(impl<$($param)*> Compactible for $ty {
+            fn compact(&mut self) {
+                self.shrink_to_fit();
+            }
+        })*
+    };
+}
+
+impl_compactible! {
+    Vec<T> => [T],
+    VecDeque<T> => [T],
+    BinaryHeap<T> => [T: Ord],
+    String => [],
+}
+
+#[cfg(feature = "std")]
+impl<K, V, S> Compactible for std::collections::HashMap<K, V, S>
+where
+    K: Eq + core::hash::Hash,
+    S: core::hash::BuildHasher,
+{
+    fn compact(&mut self) {
+        self.shrink_to_fit();
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, S> Compactible for std::collections::HashSet<T, S>
+where
+    T: Eq + core::hash::Hash,
+    S: core::hash::BuildHasher,
+{
+    fn compact(&mut self) {
+        self.shrink_to_fit();
+    }
+}
+
+impl<T: Compactible> SyncCow<T> {
+    /// Release excess capacity of the value held by each buffer. Blocks to acquire write-lock.
+    ///
+    /// A collection that grew and shrank again keeps its capacity, and so may the previous
+    /// values still held by the other buffers. An edit only replaces the value of one buffer,
+    /// so `compact` edits once per buffer, compacting each new value, until all buffers hold a
+    /// compacted one. Unlike regular edits, which prefer buffers without readers and may thus
+    /// publish into the same buffer repeatedly, it publishes into every buffer in turn, waiting
+    /// for readers of each. This costs a clone and a publish per buffer, two with the default
+    /// configuration, and bumps the [`version`](SyncCow::version) accordingly. Values still
+    /// held by readers' Arcs are only released once they drop them.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(Vec::with_capacity(1024));
+    /// cow.edit(|v| v.push(1));
+    /// cow.compact();
+    /// assert!(cow.peek(|v| v.capacity()) < 1024);
+    /// ```
+    pub fn compact(&self) {
+        let _lck = self.write_lock.lock();
+        for _ in 0..self.buffers.len() {
+            let mut cloned = self.clone_latest_locked();
+            crate::unique_mut(&mut cloned).compact();
+            // Publishing round-robin after the latest buffer reaches every buffer once
+            let next = (self.latest.load(Acquire) + 1) % self.buffers.len();
+            self.publish_into_locked(cloned, next);
+        }
+    }
+}
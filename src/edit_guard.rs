@@ -0,0 +1,63 @@
+//! Guard-style editing, see [`SyncCow::edit_guard`](crate::SyncCow::edit_guard)
+
+use crate::sync::WriteGuard;
+use crate::SyncCow;
+use alloc::sync::Arc;
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+
+/// A pending edit of a SyncCow, published when the guard is dropped.
+///
+/// Created by [`SyncCow::edit_guard`](crate::SyncCow::edit_guard). The guard holds the
+/// write-lock and derefs mutably to a clone of the value, like the argument of an
+/// [`edit`](crate::SyncCow::edit) closure. Dropping the guard publishes the clone and releases
+/// the write-lock.
+pub struct EditGuard<'a, T> {
+    cow: &'a SyncCow<T>,
+    value: Option<Arc<T>>,
+    // Declared last, so the write-lock is only released after `drop` has published
+    _lock: WriteGuard<'a>,
+}
+
+impl<'a, T> EditGuard<'a, T> {
+    pub(crate) fn new(cow: &'a SyncCow<T>, lock: WriteGuard<'a>, value: Arc<T>) -> Self {
+        EditGuard {
+            cow,
+            value: Some(value),
+            _lock: lock,
+        }
+    }
+}
+
+impl<T> Deref for EditGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value.as_ref().unwrap()
+    }
+}
+
+impl<T> DerefMut for EditGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        crate::unique_mut(self.value.as_mut().unwrap())
+    }
+}
+
+impl<T> Drop for EditGuard<'_, T> {
+    fn drop(&mut self) {
+        // Like a panicking edit closure, a panic while the guard is held publishes nothing
+        #[cfg(feature = "std")]
+        if std::thread::panicking() {
+            return;
+        }
+        if let Some(value) = self.value.take() {
+            self.cow.publish_locked(value);
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for EditGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
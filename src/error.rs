@@ -0,0 +1,37 @@
+//! Errors of the fallible edit methods, see [`SyncCowError`]
+
+use core::fmt;
+
+/// Error returned by the fallible methods of [`SyncCow`](crate::SyncCow), e.g.
+/// [`try_edit`](crate::SyncCow::try_edit),
+/// [`try_edit_timeout`](crate::SyncCow::try_edit_timeout) and
+/// [`try_edit_bounded`](crate::SyncCow::try_edit_bounded)
+///
+/// New variants may be added in minor releases, so matches need a wildcard arm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SyncCowError {
+    /// The write-lock is currently held by another writer
+    WouldBlock,
+    /// The write-lock could not be acquired before the timeout elapsed
+    Timeout,
+    /// Readers did not leave a buffer to publish into within the allowed spins
+    DrainTimeout,
+    /// The write-lock is held by the calling thread, i.e. the SyncCow is edited from within one
+    /// of its own edits. Only detected with the `std` feature.
+    Reentrant,
+}
+
+impl fmt::Display for SyncCowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncCowError::WouldBlock => write!(f, "write-lock is held by another writer"),
+            SyncCowError::Timeout => write!(f, "timed out waiting for the write-lock"),
+            SyncCowError::DrainTimeout => write!(f, "timed out waiting for readers to finish"),
+            SyncCowError::Reentrant => write!(f, "SyncCow is already being edited by this thread"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SyncCowError {}
@@ -0,0 +1,66 @@
+//! Memory usage estimates, see [`SyncCow::memory_footprint`](crate::SyncCow::memory_footprint)
+
+use crate::buffer::Buffer;
+use crate::SyncCow;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::mem::size_of;
+
+impl<T> SyncCow<T> {
+    /// Estimate the bytes of memory held by the SyncCow, for budgeting memory.
+    ///
+    /// Includes the SyncCow itself, its buffers and reader counts, which take up to 1 KiB per
+    /// buffer, and the Arc allocation of each distinct value held by the buffers, i.e. usually
+    /// two values. The estimate is shallow: memory owned by the values, e.g. the elements of a
+    /// `Vec`, isn't included. With the `get-size` feature, `memory_footprint_deep` includes it.
+    ///
+    /// Values that were replaced but are still kept alive by readers' Arcs are not included,
+    /// and neither are callbacks like those registered with [`on_change`](SyncCow::on_change).
+    ///
+    /// ```
+    /// let small = sync_cow::SyncCow::new([0u8; 0]);
+    /// let large = sync_cow::SyncCow::new([0u8; 1024]);
+    /// assert_eq!(large.memory_footprint() - small.memory_footprint(), 2 * 1024);
+    /// ```
+    #[must_use]
+    pub fn memory_footprint(&self) -> usize {
+        self.footprint_with(|_| size_of::<T>())
+    }
+
+    /// Like [`memory_footprint`](SyncCow::memory_footprint), but including the memory owned by
+    /// the values as reported by `get_size::GetSize`.
+    ///
+    /// Only available with the `get-size` feature.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(vec![0u8; 100]);
+    /// assert_eq!(cow.memory_footprint_deep() - cow.memory_footprint(), 2 * 100);
+    /// ```
+    #[cfg(feature = "get-size")]
+    #[must_use]
+    pub fn memory_footprint_deep(&self) -> usize
+    where
+        T: get_size::GetSize,
+    {
+        self.footprint_with(T::get_size)
+    }
+
+    fn footprint_with(&self, value_size: impl Fn(&T) -> usize) -> usize {
+        let buffers: usize = self
+            .buffers
+            .iter()
+            .map(|buffer| size_of::<Buffer<T>>() + buffer.allocated_size())
+            .sum();
+        // Enter all buffers, so their values can't be released while they're compared
+        let readers: Vec<_> = self.buffers.iter().map(Buffer::enter).collect();
+        // Buffers may share a value, e.g. after `from_arc`, which is only counted once
+        let distinct = readers.iter().enumerate().filter(|(index, reader)| {
+            !readers[..*index].iter().any(|other| Arc::ptr_eq(other, reader))
+        });
+        // The Arc allocation holds the strong and weak counts next to the value
+        let values: usize = distinct
+            .map(|(_, reader)| 2 * size_of::<usize>() + value_size(reader))
+            .sum();
+        size_of::<Self>() + buffers + values
+    }
+}
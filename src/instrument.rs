@@ -0,0 +1,35 @@
+//! Metrics recorded through the `metrics` crate facade if the `metrics` feature is enabled
+//!
+//! Without the feature, all functions are empty and optimized away.
+
+/// Count a published value
+#[inline]
+pub(crate) fn publish() {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("sync_cow.edits_total").increment(1);
+}
+
+/// Count a reader entering the latest buffer
+#[inline]
+pub(crate) fn read() {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("sync_cow.reads_total").increment(1);
+}
+
+/// Count the backoff iterations of a writer waiting for readers to leave a buffer
+#[inline]
+pub(crate) fn drain_spins(_spins: u64) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("sync_cow.drain_spin_iters").increment(_spins);
+}
+
+/// Acquire a lock with `lock`, recording how long the writer waited for it
+#[inline]
+pub(crate) fn write_lock_wait<G>(lock: impl FnOnce() -> G) -> G {
+    #[cfg(feature = "metrics")]
+    let start = std::time::Instant::now();
+    let guard = lock();
+    #[cfg(feature = "metrics")]
+    metrics::histogram!("sync_cow.write_lock_wait_seconds").record(start.elapsed());
+    guard
+}
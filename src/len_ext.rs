@@ -0,0 +1,81 @@
+//! Checking the size of collection values, see [`SyncCowLenExt`]
+
+use crate::SyncCow;
+use alloc::collections::{BTreeMap, BTreeSet, BinaryHeap, LinkedList, VecDeque};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Collections with a length, see [`SyncCowLenExt`].
+///
+/// Implemented for the collections of `alloc` and, with the `std` feature, `HashMap` and
+/// `HashSet`. Implement it for own collection types to use [`SyncCowLenExt`] with them.
+pub trait HasLen {
+    /// Number of elements in the collection
+    fn len(&self) -> usize;
+
+    /// Whether the collection has no elements
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+macro_rules! impl_has_len {
+    ($($ty:ty => [$($param:tt)*]),* $(,)?) => {
+        $(impl<$($param)*> HasLen for $ty {
+            fn len(&self) -> usize {
+                <$ty>::len(self)
+            }
+
+            fn is_empty(&self) -> bool {
+                <$ty>::is_empty(self)
+            }
+        })*
+    };
+}
+
+impl_has_len! {
+    Vec<T> => [T],
+    VecDeque<T> => [T],
+    LinkedList<T> => [T],
+    BinaryHeap<T> => [T],
+    BTreeSet<T> => [T],
+    BTreeMap<K, V> => [K, V],
+    String => [],
+}
+
+#[cfg(feature = "std")]
+impl_has_len! {
+    std::collections::HashMap<K, V, S> => [K, V, S],
+    std::collections::HashSet<T, S> => [T, S],
+}
+
+/// Size of a SyncCow holding a collection.
+///
+/// Both methods [`peek`](SyncCow::peek) at the current value, so they are lock-less and don't
+/// clone the Arc. With concurrent writers, the size may have changed by the time it's returned.
+///
+/// ```
+/// use sync_cow::{SyncCow, SyncCowLenExt};
+///
+/// let names = SyncCow::new(vec!["moo"]);
+/// assert_eq!(names.len(), 1);
+/// names.edit(|names| names.clear());
+/// assert!(names.is_empty());
+/// ```
+pub trait SyncCowLenExt {
+    /// Number of elements in the current value
+    fn len(&self) -> usize;
+
+    /// Whether the current value has no elements
+    fn is_empty(&self) -> bool;
+}
+
+impl<T: HasLen> SyncCowLenExt for SyncCow<T> {
+    fn len(&self) -> usize {
+        self.peek(T::len)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.peek(T::is_empty)
+    }
+}
@@ -7,7 +7,8 @@
 //! any read-access. A SyncCow with only one writer and arbitrary readers will never block. 
 //! As SyncCow stores two copies of it's contained value and read values are handed out as
 //! std::sync::Arc, a program using SyncCow might have a higher memory-footprint compared to
-//! std::sync::RwLock.
+//! std::sync::RwLock. Each buffer also counts its readers in one 64 byte cache line per
+//! available core, up to 16, i.e. up to 1 KiB per buffer.
 //!
 //! Note that readers might read outdated data when using the SyncCow,
 //! as writing and reading concurrently is possible.
@@ -18,130 +19,2001 @@
 //! ```
 #![doc = include_str!("../examples/write_and_read_thread.rs")]
 //! ```
+//!
+//! ## no_std
+//!
+//! SyncCow only needs `core` and `alloc`, except for the write-lock. Disable the default `std`
+//! feature and enable the `spin` feature to use a spin-lock as write-lock instead of
+//! `std::sync::Mutex`. All methods remain available, writers spin instead of yielding their
+//! thread while waiting for readers.
+#![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
+
+#[cfg(not(any(feature = "std", feature = "spin")))]
+compile_error!("sync_cow requires either the `std` or the `spin` feature for its write-lock");
+
+extern crate alloc;
 
-use std::sync::atomic::Ordering::Relaxed;
-use std::sync::atomic::{AtomicPtr, AtomicUsize};
-use std::sync::{Arc, Mutex};
+use alloc::boxed::Box;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::sync::atomic::Ordering::{Acquire, Relaxed, Release, SeqCst};
 
-#[cfg(test)]
+#[cfg(feature = "arc-swap")]
+mod arc_swap_impl;
+#[cfg(feature = "async")]
+mod async_edit;
+mod buffer;
+mod builder;
+#[cfg(feature = "std")]
+mod changes;
+mod compact;
+mod edit_guard;
+mod error;
+mod footprint;
+mod instrument;
+#[cfg(feature = "serde")]
+mod serde_impl;
+#[cfg(all(test, loom))]
+mod loom_tests;
+mod len_ext;
+mod map_ext;
+mod mapped;
+mod option;
+mod pair;
+mod read_guard;
+mod read_ref;
+mod snapshot;
+mod sync;
+#[cfg(all(test, feature = "std", not(loom)))]
 mod tests;
+#[cfg(feature = "async")]
+mod watch;
+
+use buffer::{BoxAlloc, Buffer, Reader};
+use sync::{fence, AtomicU64, AtomicUsize, Mutex, WriteLock};
+#[cfg(feature = "std")]
+use sync::Notifier;
+
+pub use builder::{Backoff, SyncCowBuilder};
+#[cfg(feature = "std")]
+pub use changes::ChangeIter;
+pub use compact::Compactible;
+pub use edit_guard::EditGuard;
+pub use error::SyncCowError;
+pub use len_ext::{HasLen, SyncCowLenExt};
+pub use map_ext::SyncCowMapExt;
+pub use option::SyncCowOption;
+pub use pair::edit_pair;
+pub use mapped::MappedArc;
+pub use read_guard::ReadGuard;
+pub use read_ref::ReadRef;
+pub use snapshot::Snapshot;
 
 /// Thread-safe clone-on-write container with lock-less reading. 
 ///
 /// See crate documentation for a full code example
-pub struct SyncCow<T: Clone> {
-    write_lock: Mutex<()>,
+///
+/// `SyncCow<T>` is `Send` and `Sync` exactly when `T` is `Send + Sync`, because readers on any
+/// thread get their own `Arc<T>` of the contained value:
+///
+/// ```
+/// fn assert_send_sync<S: Send + Sync>() {}
+/// assert_send_sync::<sync_cow::SyncCow<i32>>();
+/// ```
+///
+/// ```compile_fail
+/// fn assert_send<S: Send>() {}
+/// assert_send::<sync_cow::SyncCow<std::rc::Rc<u8>>>();
+/// ```
+///
+/// ```compile_fail
+/// fn assert_sync<S: Sync>() {}
+/// assert_sync::<sync_cow::SyncCow<std::rc::Rc<u8>>>();
+/// ```
+pub struct SyncCow<T> {
+    write_lock: WriteLock,
+    observers: Mutex<Vec<Observer<T>>>,
+    cloner: Option<Cloner<T>>,
+    #[cfg(feature = "profiling")]
+    clone_observer: Mutex<Option<CloneObserver>>,
+    #[cfg(feature = "std")]
+    notifier: Notifier,
+    #[cfg(feature = "async")]
+    async_write_lock: tokio::sync::Mutex<()>,
+    /// Channel forwarding published values, created by the first [`SyncCow::watch`]
+    #[cfg(feature = "async")]
+    watch: std::sync::OnceLock<tokio::sync::watch::Sender<Arc<T>>>,
     latest: AtomicUsize,
-    atomic_red: (AtomicPtr<Arc<T>>, AtomicUsize),
-    atomic_green: (AtomicPtr<Arc<T>>, AtomicUsize),
+    /// Buffer holding the value published before the latest one, or [`NO_PREVIOUS`] while a
+    /// writer publishes into it
+    previous: AtomicUsize,
+    version: AtomicU64,
+    /// Creation time, the base of `modified`
+    #[cfg(feature = "std")]
+    created: std::time::Instant,
+    /// Nanoseconds from `created` to the latest publish
+    #[cfg(feature = "std")]
+    modified: AtomicU64,
+    buffers: Box<[Buffer<T>]>,
+    backoff: Backoff,
+}
+
+/// Marks [`SyncCow::previous`] while a writer replaces the previous value
+const NO_PREVIOUS: usize = usize::MAX;
+
+/// Callback registered with [`SyncCow::on_change`]
+type Observer<T> = Box<dyn Fn(&Arc<T>) + Send + Sync>;
+
+/// Clone function used by edits
+enum Cloner<T> {
+    /// `T::clone` of SyncCows created from a `Clone` value
+    Clone(fn(&T) -> T),
+    /// Function set with [`SyncCow::with_cloner`], shared by clones of the SyncCow
+    Custom(Arc<dyn Fn(&T) -> T + Send + Sync>),
+}
+
+impl<T> Clone for Cloner<T> {
+    fn clone(&self) -> Self {
+        match self {
+            Cloner::Clone(clone) => Cloner::Clone(*clone),
+            Cloner::Custom(cloner) => Cloner::Custom(cloner.clone()),
+        }
+    }
+}
+
+impl<T> Cloner<T> {
+    fn clone_value(&self, value: &T) -> T {
+        match self {
+            Cloner::Clone(clone) => clone(value),
+            Cloner::Custom(cloner) => cloner(value),
+        }
+    }
+}
+
+/// Cloner of SyncCows created from a `Clone` value
+fn clone_cloner<T: Clone>() -> Option<Cloner<T>> {
+    Some(Cloner::Clone(T::clone))
 }
 
-const RED: usize = 0;
-const GREEN: usize = 1;
+/// Mutable access to an edit's fresh clone, which no one else holds an Arc of yet
+fn unique_mut<T>(cloned: &mut Arc<T>) -> &mut T {
+    Arc::get_mut(cloned).expect("the clone of an edit is unique")
+}
+
+/// Callback registered with [`SyncCow::set_clone_observer`]
+#[cfg(feature = "profiling")]
+type CloneObserver = Box<dyn Fn(std::time::Duration) + Send + Sync>;
 
-impl<T: Clone> SyncCow<T> {
+impl<T> SyncCow<T> {
     /// Edit the contents of the SyncCow. Blocks to acquire write-lock.
     ///
     /// The edit function will block until the current writer is done and the write-lock could be
     /// acquired. Once the lock has been acquired, the contained object is cloned, and `edit_fn` is
     /// called with the cloned object as argument. After the `edit_fn` has returned, the write-lock
     /// is released and the internal object pointer is updated so readers read the cloned-and-edited object.
+    /// Whatever `edit_fn` returns is handed back to the caller once the edit has been published.
+    ///
+    /// The object is cloned with `T::clone`, or with the cloner set with
+    /// [`with_cloner`](SyncCow::with_cloner), so `T` only needs to be `Clone` for the
+    /// constructors. Editing a SyncCow created with [`from_arc`](SyncCow::from_arc) panics, as it
+    /// has no cloner.
+    ///
+    /// If `edit_fn` panics, the cloned object is dropped and nothing is published: readers keep
+    /// reading the previous value and later edits work as usual.
+    ///
+    /// `edit_fn` must not edit the same SyncCow again, which would deadlock on the write-lock.
+    /// With the `std` feature this is detected and panics with "reentrant edit detected" instead.
+    /// Reading the SyncCow from within `edit_fn` returns the value from before the edit, as the
+    /// edited value is only published after `edit_fn` returns.
+    ///
+    /// `edit_fn` runs before `edit` returns, so it doesn't need to be `'static` and can borrow
+    /// locals, also mutably. See [`edit_ctx`](SyncCow::edit_ctx) to pass such state explicitly.
     ///
     /// ```
     /// let cow = sync_cow::SyncCow::new(5);
     /// cow.edit(|x| *x = 6);
     /// assert_eq!(*cow.read(), 6);
     /// ```
-    pub fn edit<F>(&self, edit_fn: F)
+    ///
+    /// ```
+    /// # use std::collections::HashMap;
+    /// let cow = sync_cow::SyncCow::new(HashMap::new());
+    /// let (k, v) = ("moo", 1);
+    /// let inserted = cow.edit(|m| m.insert(k, v).is_none());
+    /// assert!(inserted);
+    /// ```
+    pub fn edit<F, R>(&self, edit_fn: F) -> R
     where
-        F: FnOnce(&mut T),
+        F: FnOnce(&mut T) -> R,
     {
         // The write-lock prevents multiple concurrent writers, but does not inhibit readers
-        let _lck = self.write_lock.lock().unwrap();
-        let latest = self.latest.load(Relaxed);
-
-        // We need to clone latest, but update the older pointer.
-        let ((old_ptr, old_cnt), latest_ptr) = match latest {
-            RED => (&self.atomic_green, &self.atomic_red.0),
-            GREEN => (&self.atomic_red, &self.atomic_green.0),
-            _ => panic!("Latest does not exist. This should never happen."),
-        };
+        let _lck = self.write_lock.lock();
+        self.edit_locked(edit_fn)
+    }
 
-        // Clone latest
-        let load_ptr = latest_ptr.load(Relaxed);
-        let obj = unsafe { &*load_ptr };
-        let mut cloned = Box::new(Arc::new(obj.as_ref().clone()));
+    /// Edit the contents of the SyncCow with a borrowed closure. Blocks to acquire write-lock.
+    ///
+    /// Like [`edit`](SyncCow::edit), `edit_fn` is called exactly once, but it's only borrowed, so
+    /// a closure keeping mutable state can be reused across edits, e.g. in a retry loop.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(5);
+    /// let mut edits = 0;
+    /// let mut increment = |x: &mut i32| {
+    ///     edits += 1;
+    ///     *x += 1;
+    /// };
+    /// cow.edit_mut(&mut increment);
+    /// cow.edit_mut(&mut increment);
+    /// assert_eq!(edits, 2);
+    /// assert_eq!(*cow.read(), 7);
+    /// ```
+    pub fn edit_mut<F, R>(&self, edit_fn: &mut F) -> R
+    where
+        F: FnMut(&mut T) -> R,
+    {
+        self.edit(edit_fn)
+    }
 
-        // And let the user-provided callback edit it
-        edit_fn(&mut Arc::get_mut(cloned.as_mut()).unwrap());
+    /// Edit the contents of the SyncCow, passing `ctx` to the edit function. Blocks to acquire
+    /// write-lock.
+    ///
+    /// Like [`edit`](SyncCow::edit), but `edit_fn` gets mutable access to `ctx` next to the
+    /// cloned object, e.g. to collect what an edit removed without capturing it in the closure.
+    /// Changes to `ctx` are kept even if `edit_fn` panics and nothing is published.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(vec![1, 2, 3, 4]);
+    /// let mut removed: Vec<i32> = Vec::new();
+    /// cow.edit_ctx(&mut removed, |v, removed| {
+    ///     removed.extend(v.iter().filter(|x| **x % 2 == 0));
+    ///     v.retain(|x| x % 2 != 0);
+    /// });
+    /// assert_eq!(removed, [2, 4]);
+    /// assert_eq!(*cow.read(), [1, 3]);
+    /// ```
+    pub fn edit_ctx<C, F, R>(&self, ctx: &mut C, edit_fn: F) -> R
+    where
+        F: FnOnce(&mut T, &mut C) -> R,
+    {
+        self.edit(|value| edit_fn(value, ctx))
+    }
+
+    /// Edit the contents of the SyncCow through a guard, like `RwLock::write`. Blocks to acquire
+    /// write-lock.
+    ///
+    /// Acquires the write-lock and clones the latest value like [`edit`](SyncCow::edit), but
+    /// returns the clone in an [`EditGuard`] instead of passing it to a closure. The guard derefs
+    /// mutably to the clone, and publishes it and releases the write-lock when dropped. Readers
+    /// keep reading the previous value until then.
+    ///
+    /// With the `std` feature, a guard dropped while unwinding from a panic publishes nothing,
+    /// like a panicking `edit` closure. A leaked guard, e.g. through `mem::forget`, never
+    /// publishes and keeps the write-lock held, so all later edits block forever.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(vec![1]);
+    /// let mut guard = cow.edit_guard();
+    /// guard.push(2);
+    /// assert_eq!(*cow.read(), [1]);
+    /// drop(guard);
+    /// assert_eq!(*cow.read(), [1, 2]);
+    /// ```
+    #[must_use = "dropping the guard right away publishes the unchanged value"]
+    pub fn edit_guard(&self) -> EditGuard<'_, T> {
+        let lock = self.write_lock.lock();
+        EditGuard::new(self, lock, self.clone_latest_locked())
+    }
+
+    /// Apply several edits to the contents of the SyncCow at once. Blocks to acquire write-lock.
+    ///
+    /// Like [`edit`](SyncCow::edit), but the contained object is cloned only once, all `edits`
+    /// are applied to the clone in order, and the result is published as a single new version.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(5);
+    /// cow.edit_batch([|x: &mut i32| *x += 1, |x: &mut i32| *x *= 2]);
+    /// assert_eq!(*cow.read(), 12);
+    /// assert_eq!(cow.version(), 1);
+    /// ```
+    pub fn edit_batch<I, F>(&self, edits: I)
+    where
+        I: IntoIterator<Item = F>,
+        F: FnOnce(&mut T),
+    {
+        self.edit(|obj| edits.into_iter().for_each(|edit_fn| edit_fn(obj)));
+    }
+
+    /// Append the items of `iter` to the contained collection. Blocks to acquire write-lock.
+    ///
+    /// Like [`edit`](SyncCow::edit) with `Extend::extend`: the collection is cloned once and
+    /// all items are published together as a single new version.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(vec![1]);
+    /// cow.extend([2, 3]);
+    /// assert_eq!(*cow.read(), [1, 2, 3]);
+    /// assert_eq!(cow.version(), 1);
+    /// ```
+    pub fn extend<A, I>(&self, iter: I)
+    where
+        T: Extend<A>,
+        I: IntoIterator<Item = A>,
+    {
+        self.edit(|obj| obj.extend(iter));
+    }
 
-        // This releases the pointer of the Arc from the Box, such that it is not automatically freed
-        let new_ptr = Box::into_raw(cloned);
+    /// Try to edit the contents of the SyncCow without blocking.
+    ///
+    /// Behaves like [`edit`](SyncCow::edit), but returns [`SyncCowError::WouldBlock`] immediately
+    /// if another writer currently holds the write-lock. In that case the contained object is not
+    /// cloned and `edit_fn` is not called.
+    ///
+    /// Like all `try_edit` variants, an edit from within an edit of the same SyncCow returns
+    /// [`SyncCowError::Reentrant`] instead of panicking like [`edit`](SyncCow::edit).
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(5);
+    /// assert!(cow.try_edit(|x| *x = 6).is_ok());
+    /// assert_eq!(*cow.read(), 6);
+    /// ```
+    #[must_use = "the edit is not applied if the write-lock was not acquired"]
+    pub fn try_edit<F>(&self, edit_fn: F) -> Result<(), SyncCowError>
+    where
+        F: FnOnce(&mut T),
+    {
+        if self.write_lock.is_reentrant() {
+            return Err(SyncCowError::Reentrant);
+        }
+        let _lck = self.write_lock.try_lock().ok_or(SyncCowError::WouldBlock)?;
+        self.edit_locked(edit_fn);
+        Ok(())
+    }
 
-        // Override the old ptr, let the previous "latest_ptr" still be read by late readers
-        let old_ptr = old_ptr.swap(new_ptr, Relaxed);
+    /// Edit the contents of the SyncCow, but only publish the edit if `edit_fn` returns `true`.
+    /// Blocks to acquire write-lock.
+    ///
+    /// Like [`edit`](SyncCow::edit), but if `edit_fn` returns `false` the edited clone is dropped
+    /// and readers keep reading the current value, without a new version being published.
+    /// Returns whether the edit was published.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(vec![1]);
+    /// let published = cow.edit_if(|v| {
+    ///     if v.contains(&1) {
+    ///         return false;
+    ///     }
+    ///     v.push(1);
+    ///     true
+    /// });
+    /// assert!(!published);
+    /// assert_eq!(cow.version(), 0);
+    /// ```
+    #[must_use = "the edit may not have been published"]
+    pub fn edit_if<F>(&self, edit_fn: F) -> bool
+    where
+        F: FnOnce(&mut T) -> bool,
+    {
+        let _lck = self.write_lock.lock();
+        let mut cloned = self.clone_latest_locked();
+        let publish = edit_fn(unique_mut(&mut cloned));
+        if publish {
+            self.publish_locked(cloned);
+        }
+        publish
+    }
 
-        // And wait until any late readers still reading the older ptr finished cloning the Arc
-        while old_cnt.load(Relaxed) != 0 {
-            std::thread::yield_now();
+    /// Edit the contents of the SyncCow, but only publish the edit if it changed the value.
+    /// Blocks to acquire write-lock.
+    ///
+    /// Like [`edit_if`](SyncCow::edit_if), but instead of `edit_fn` deciding, the edited clone
+    /// is compared to the current value. No-op edits then neither bump the
+    /// [`version`](SyncCow::version) nor make readers pick up an equal value. Returns whether
+    /// the edit was published.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(5);
+    /// assert!(!cow.edit_dedup(|x| *x = 5));
+    /// assert_eq!(cow.version(), 0);
+    /// assert!(cow.edit_dedup(|x| *x = 6));
+    /// assert_eq!(cow.version(), 1);
+    /// ```
+    #[must_use = "the edit may not have been published"]
+    pub fn edit_dedup<F>(&self, edit_fn: F) -> bool
+    where
+        T: PartialEq,
+        F: FnOnce(&mut T),
+    {
+        let _lck = self.write_lock.lock();
+        let mut cloned = self.clone_latest_locked();
+        edit_fn(unique_mut(&mut cloned));
+        let changed = cloned != *self.latest_locked();
+        if changed {
+            self.publish_locked(cloned);
         }
+        changed
+    }
 
-        // Now guide all readers to the newly updated Arc
-        self.latest.store((latest + 1) % 2, Relaxed);
+    /// Replace the contents of the SyncCow without cloning the current value. Blocks to acquire
+    /// write-lock.
+    ///
+    /// Like [`edit`](SyncCow::edit), but `value` is moved into the SyncCow as is, so the
+    /// contained object is never cloned. Prefer this over `edit` when building a fresh value anyway.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(vec![1, 2, 3]);
+    /// cow.set(vec![4]);
+    /// assert_eq!(*cow.read(), vec![4]);
+    /// ```
+    pub fn set(&self, value: T) {
+        let _lck = self.write_lock.lock();
+        self.publish_locked(Arc::new(value));
+    }
 
-        // Ensures Arc pointed to by old_ptr will be released at return
-        let _ = unsafe { Box::from_raw(old_ptr) };
+    /// Replace the contents of the SyncCow like [`set`](SyncCow::set), then wait until no reader
+    /// accesses any buffer anymore. Blocks to acquire write-lock.
+    ///
+    /// When `reset` returns, all reads, [`peek`](SyncCow::peek) closures and
+    /// [`borrow`](SyncCow::borrow) guards that started before the new value was published have
+    /// finished, so none of them accesses a previous value through the SyncCow anymore, e.g.
+    /// before freeing resources associated with it. Arcs returned by [`read`](SyncCow::read)
+    /// may still hold previous values.
+    ///
+    /// This is a stronger guarantee than `set` gives, which only waits for readers of the
+    /// buffer it publishes into. `reset` also waits for readers of the latest buffer, which
+    /// may take long while readers keep entering it, and blocks other writers meanwhile. It
+    /// must not be called while the same thread borrows the SyncCow, as it would wait forever.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(vec![1, 2]);
+    /// cow.reset(vec![]);
+    /// assert!(cow.read().is_empty());
+    /// ```
+    pub fn reset(&self, value: T) {
+        let _lck = self.write_lock.lock();
+        self.publish_locked(Arc::new(value));
+        for buffer in self.buffers.iter() {
+            buffer.drain(self.backoff);
+        }
     }
 
-    /// Get the current value of the SyncCow as immutable std::sync::Arc.
+    /// Replace the contents of the SyncCow and return the previous value. Blocks to acquire
+    /// write-lock.
     ///
-    /// The `read` function will return the latest version of the SyncCow's value as an Arc.
-    /// This Arc contains an immutable state of the SyncCow's value; call `read()` again to obtain
-    /// an updated state after a writer has edited the value.
-    /// The reader can decide when to drop the Arc; the value will be dropped when a writer has
-    /// updated the value and no reader keeps an Arc of this value-state alive.
+    /// Like [`set`](SyncCow::set), but hands back the Arc readers were seeing before the call,
+    /// similar to `std::mem::replace`.
     ///
     /// ```
     /// let cow = sync_cow::SyncCow::new(5);
-    /// let val = cow.read();
-    /// assert_eq!(*val, 5);
-    /// cow.edit(|x| *x = 6);
-    /// assert_eq!(*val, 5);  // Arc keeps old value
-    /// assert_eq!(*cow.read(), 6); // Another read returns new value
+    /// let old = cow.replace(6);
+    /// assert_eq!(*old, 5);
+    /// assert_eq!(*cow.read(), 6);
     /// ```
-    pub fn read(&self) -> Arc<T> {
-        let latest = self.latest.load(Relaxed);
-        // We want to read whatever has been updated last
-        let (ptr, cnt) = match latest {
-            RED => &self.atomic_red,
-            GREEN => &self.atomic_green,
-            _ => panic!("Latest does not exist. This should never happen."),
+    pub fn replace(&self, value: T) -> Arc<T> {
+        let _lck = self.write_lock.lock();
+        let previous = self.latest_locked().clone();
+        self.publish_locked(Arc::new(value));
+        previous
+    }
+
+    /// Exchange the contents of two SyncCows. Blocks to acquire both write-locks.
+    ///
+    /// Neither value is cloned, each SyncCow publishes the Arc the other held. To avoid a
+    /// deadlock between `a.swap(&b)` and `b.swap(&a)` running concurrently, the write-locks are
+    /// always acquired in the order of the SyncCows' addresses. Readers of either SyncCow may
+    /// observe one SyncCow already swapped and the other not yet.
+    ///
+    /// ```
+    /// let a = sync_cow::SyncCow::new(1);
+    /// let b = sync_cow::SyncCow::new(2);
+    /// a.swap(&b);
+    /// assert_eq!((*a.read(), *b.read()), (2, 1));
+    /// ```
+    pub fn swap(&self, other: &SyncCow<T>) {
+        if core::ptr::eq(self, other) {
+            return;
+        }
+        let (first, second) = if (self as *const Self) < (other as *const Self) {
+            (self, other)
+        } else {
+            (other, self)
         };
+        let _first_lck = first.write_lock.lock();
+        let _second_lck = second.write_lock.lock();
 
-        // Notify the writer we're cloning the Arc, so it waits before releasing it.
-        cnt.fetch_add(1, Relaxed);
-        let arc = unsafe { &*ptr.load(Relaxed) }.clone();
-        cnt.fetch_sub(1, Relaxed);
-        arc
+        let ours = self.latest_locked().clone();
+        let theirs = other.latest_locked().clone();
+        self.publish_locked(theirs);
+        other.publish_locked(ours);
     }
 
-    pub fn new(obj: T) -> SyncCow<T> {
-        let red = Box::new(Arc::new(obj.clone()));
-        let green = Box::new(Arc::new(obj.clone()));
-        SyncCow {
-            // moooo
-            latest: AtomicUsize::new(0),
-            write_lock: Mutex::new(()),
-            atomic_red: (AtomicPtr::new(Box::into_raw(red)), AtomicUsize::new(0)),
-            atomic_green: (AtomicPtr::new(Box::into_raw(green)), AtomicUsize::new(0)),
+    /// Publish values computed by `f` from the current value, retrying if another writer
+    /// published in between.
+    ///
+    /// Like `AtomicUsize::fetch_update`: `f` is called with the current value without holding the
+    /// write-lock. If it returns `Some(new)`, `new` is published unless another writer published
+    /// since the current value was read, in which case `f` is called again with the newer value.
+    /// Returns `Ok` with the value `new` replaced, or `Err` with the current value if `f` returned
+    /// `None`. As `f` may be called several times, it should not have side effects.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(5);
+    /// assert_eq!(*cow.fetch_update(|x| Some(x + 1)).unwrap(), 5);
+    /// assert_eq!(*cow.fetch_update(|_| None).unwrap_err(), 6);
+    /// ```
+    #[must_use = "the update is not applied if `f` returned `None`"]
+    pub fn fetch_update<F>(&self, mut f: F) -> Result<Arc<T>, Arc<T>>
+    where
+        F: FnMut(&T) -> Option<T>,
+    {
+        loop {
+            // Reading the version first, the value is at least as new as the version
+            let version = self.version();
+            let current = self.read();
+            let new = match f(&current) {
+                Some(new) => new,
+                None => return Err(current),
+            };
+
+            let _lck = self.write_lock.lock();
+            // An unchanged version witnesses that no writer published since `current` was read
+            if self.version.load(Acquire) == version {
+                self.publish_locked(Arc::new(new));
+                return Ok(current);
+            }
         }
     }
-}
 
-impl<T: Clone> Drop for SyncCow<T> {
-    fn drop(&mut self) {
-        // The Arcs are released Boxes, so we need to make sure they're freed again
-        let _ = unsafe { Box::from_raw(self.atomic_red.0.load(Relaxed)) };
-        let _ = unsafe { Box::from_raw(self.atomic_green.0.load(Relaxed)) };
+    /// Replace the contents of the SyncCow with a value computed from the current one. Blocks to
+    /// acquire write-lock.
+    ///
+    /// Unlike [`edit`](SyncCow::edit), the current value is not cloned: `update_fn` gets a shared
+    /// reference and returns the new value, which is published as is. This suits persistent data
+    /// structures, where building a new version from the old one is cheaper than clone-and-mutate.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(5);
+    /// cow.update(|x| x + 1);
+    /// assert_eq!(*cow.read(), 6);
+    /// ```
+    pub fn update<F>(&self, update_fn: F)
+    where
+        F: FnOnce(&T) -> T,
+    {
+        let _lck = self.write_lock.lock();
+        let new = update_fn(self.latest_locked());
+        self.publish_locked(Arc::new(new));
     }
-}
+
+    /// Replace the contents of the SyncCow with `new` if they equal `expected`. Blocks to acquire
+    /// write-lock.
+    ///
+    /// The comparison and the publication both happen under the write-lock, so no other writer can
+    /// slip in between. If the current value differs from `expected`, nothing is published and
+    /// the current Arc is returned as `Err`, so the caller can retry based on what it observed.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(5);
+    /// assert!(cow.compare_and_set(&5, 6).is_ok());
+    /// assert_eq!(*cow.compare_and_set(&5, 7).unwrap_err(), 6);
+    /// assert_eq!(*cow.read(), 6);
+    /// ```
+    #[must_use = "the value is not set if it didn't equal `expected`"]
+    pub fn compare_and_set(&self, expected: &T, new: T) -> Result<(), Arc<T>>
+    where
+        T: PartialEq,
+    {
+        let _lck = self.write_lock.lock();
+        let current = self.latest_locked();
+        if current.as_ref() != expected {
+            return Err(Arc::clone(current));
+        }
+        self.publish_locked(Arc::new(new));
+        Ok(())
+    }
+
+    /// Publish `new` only if the SyncCow is still at version `expected_version`. Blocks to
+    /// acquire write-lock.
+    ///
+    /// Like [`compare_and_set`](SyncCow::compare_and_set), but compares the
+    /// [`version`](SyncCow::version) instead of the value, which needs no `PartialEq` and is
+    /// cheap for any `T`. Returns the new version on success. Otherwise `new` is dropped and
+    /// the current version and value are returned, so a retry loop doesn't need to read again.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(5);
+    /// let version = cow.version();
+    /// assert_eq!(cow.compare_exchange(version, 6), Ok(1));
+    /// let (current, value) = cow.compare_exchange(version, 7).unwrap_err();
+    /// assert_eq!((current, *value), (1, 6));
+    /// ```
+    #[must_use = "the value is not set if the version didn't equal `expected_version`"]
+    pub fn compare_exchange(&self, expected_version: u64, new: T) -> Result<u64, (u64, Arc<T>)> {
+        let _lck = self.write_lock.lock();
+        // Only writers modify the version, so it's stable while holding the write-lock
+        let version = self.version();
+        if version != expected_version {
+            return Err((version, Arc::clone(self.latest_locked())));
+        }
+        self.publish_locked(Arc::new(new));
+        Ok(version + 1)
+    }
+
+    /// Try to edit the contents of the SyncCow, waiting at most `timeout` for the write-lock.
+    ///
+    /// Behaves like [`try_edit`](SyncCow::try_edit), but retries acquiring the write-lock until
+    /// `timeout` has elapsed, yielding the thread in between. Returns [`SyncCowError::Timeout`]
+    /// if the write-lock could not be acquired in time, without cloning or calling `edit_fn`.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(5);
+    /// let timeout = std::time::Duration::from_millis(10);
+    /// assert!(cow.try_edit_timeout(timeout, |x| *x = 6).is_ok());
+    /// assert_eq!(*cow.read(), 6);
+    /// ```
+    #[cfg(feature = "std")]
+    #[must_use = "the edit is not applied if the write-lock was not acquired"]
+    pub fn try_edit_timeout<F>(
+        &self,
+        timeout: std::time::Duration,
+        edit_fn: F,
+    ) -> Result<(), SyncCowError>
+    where
+        F: FnOnce(&mut T),
+    {
+        if self.write_lock.is_reentrant() {
+            return Err(SyncCowError::Reentrant);
+        }
+        let start = std::time::Instant::now();
+        let mut step = 0;
+        let _lck = loop {
+            if let Some(lck) = self.write_lock.try_lock() {
+                break lck;
+            }
+            if start.elapsed() >= timeout {
+                return Err(SyncCowError::Timeout);
+            }
+            sync::backoff(self.backoff, step);
+            step += 1;
+        };
+        self.edit_locked(edit_fn);
+        Ok(())
+    }
+
+    /// Try to edit the contents of the SyncCow, waiting for readers for at most `max_spins`
+    /// backoff steps. Blocks to acquire write-lock.
+    ///
+    /// A writer publishes into a buffer not holding the latest value, and has to wait until
+    /// readers of that buffer are done, e.g. a reader preempted while reading, or a long
+    /// [`peek`](SyncCow::peek) that started while the buffer still held the latest value. This
+    /// waits at most `max_spins` steps of the configured [`Backoff`] for such a buffer to become
+    /// free. Otherwise it returns [`SyncCowError::DrainTimeout`] without cloning, calling
+    /// `edit_fn` or publishing anything.
+    ///
+    /// Once a free buffer was found, the edit proceeds like [`edit`](SyncCow::edit). Readers
+    /// entering that buffer in the meantime leave it right away, as it doesn't hold the latest
+    /// value, so the remaining wait is short.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(5);
+    /// assert!(cow.try_edit_bounded(100, |x| *x = 6).is_ok());
+    /// assert_eq!(*cow.read(), 6);
+    /// ```
+    #[must_use = "the edit is not applied if readers did not leave a buffer in time"]
+    pub fn try_edit_bounded<F>(&self, max_spins: usize, edit_fn: F) -> Result<(), SyncCowError>
+    where
+        F: FnOnce(&mut T),
+    {
+        if self.write_lock.is_reentrant() {
+            return Err(SyncCowError::Reentrant);
+        }
+        let _lck = self.write_lock.lock();
+        let latest = self.latest.load(Acquire);
+        let mut spins = 0;
+        // Only a heuristic like `next_buffer_locked`, `publish_locked` still drains the buffer
+        while self
+            .buffers
+            .iter()
+            .enumerate()
+            .all(|(index, buffer)| index == latest || buffer.has_readers())
+        {
+            if spins == max_spins {
+                return Err(SyncCowError::DrainTimeout);
+            }
+            sync::backoff(self.backoff, spins as u64);
+            spins += 1;
+        }
+        self.edit_locked(edit_fn);
+        Ok(())
+    }
+
+    /// Clone latest, edit and publish it. The caller must hold the write-lock.
+    fn edit_locked<F, R>(&self, edit_fn: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        let mut cloned = self.clone_latest_locked();
+
+        // And let the user-provided callback edit it
+        let result = edit_fn(unique_mut(&mut cloned));
+
+        self.publish_locked(cloned);
+        result
+    }
+
+    /// Clone the latest value into a new Arc with the cloner. The caller must hold the
+    /// write-lock.
+    ///
+    /// Panics if the SyncCow has no cloner, i.e. it was created with `from_arc` or
+    /// `from_arc_swap` from a value that may not be `Clone`.
+    fn clone_latest_locked(&self) -> Arc<T> {
+        let cloner = self.cloner.as_ref().expect(
+            "SyncCow has no cloner to edit the value: create it with with_cloner or from a Clone value",
+        );
+        let clone = || cloner.clone_value(self.latest_locked());
+        #[cfg(feature = "profiling")]
+        if let Some(observer) = self.clone_observer.lock().as_ref() {
+            let start = std::time::Instant::now();
+            let cloned = clone();
+            observer(start.elapsed());
+            return Arc::new(cloned);
+        }
+        Arc::new(clone())
+    }
+
+    /// Get the latest Arc. The caller must hold the write-lock, which keeps the latest buffer
+    /// from being replaced or freed while the reference is alive.
+    fn latest_locked(&self) -> &Arc<T> {
+        // Only writers store `latest` and they are serialized by the write-lock, so this load
+        // always sees the previous writer's store. Acquire keeps it explicit without relying on it.
+        let latest = &self.buffers[self.latest.load(Acquire)];
+
+        // Acquire pairs with the swap of the writer that published this pointer.
+        unsafe { &*latest.ptr.load(Acquire) }
+    }
+
+    /// Publish `new` as the latest value. The caller must hold the write-lock.
+    fn publish_locked(&self, new: Arc<T>) {
+        // We publish into an older buffer, the latest one stays readable meanwhile.
+        self.publish_into_locked(new, self.next_buffer_locked(self.latest.load(Acquire)));
+    }
+
+    /// Publish `new` into the buffer at index `next`, which must not be the latest one. The
+    /// caller must hold the write-lock.
+    fn publish_into_locked(&self, new: Arc<T>, next: usize) {
+        let latest = self.latest.load(Acquire);
+        debug_assert_ne!(next, latest, "publishing into the latest buffer");
+        let buffer = &self.buffers[next];
+
+        // Only writers modify `previous`. If we're about to replace the previous value, readers
+        // of it must not pick up the new one. They observe the mark at the latest once they
+        // load the new pointer, as the Release swap below orders the mark before it.
+        if self.previous.load(Relaxed) == next {
+            self.previous.store(NO_PREVIOUS, Relaxed);
+        }
+
+        // Override the old ptr, let the previous "latest_ptr" still be read by late readers.
+        let old_ptr = buffer.swap(new);
+
+        // The swap above and the reader count loads in `drain` form a store-load (Dekker) pair
+        // with the reader's increment and pointer load, separated by SeqCst fences on both sides.
+        // Either `drain` observes a late reader's increment, or that reader's pointer load
+        // observes the new pointer and never touches `old_ptr`.
+        fence(SeqCst);
+
+        // And wait until any late readers still reading the older ptr finished cloning the Arc.
+        // These are readers that loaded `latest` back when it was `next`: they counted
+        // themselves in `buffer` and load `buffer`'s pointer, so the counts drained are the ones
+        // of the buffer whose pointer was swapped. Readers of the latest buffer aren't waited for.
+        buffer.drain(self.backoff);
+
+        // `previous` must be stored first: readers seeing the mark read the latest value, which
+        // must not be the new one yet, unless the mark is gone by the time they check it again.
+        self.previous.store(latest, Release);
+
+        // Now guide all readers to the newly updated Arc. Release publishes the swap above to
+        // readers that Acquire `latest`.
+        self.latest.store(next, Release);
+
+        // Release the Arc pointed to by old_ptr before running user code that may panic
+        unsafe { buffer.release(old_ptr) };
+
+        self.announce_locked();
+    }
+
+    /// Bump the version and notify waiters and observers of the value just made the latest one.
+    /// The caller must hold the write-lock.
+    fn announce_locked(&self) {
+        // Only writers modify the version, so a plain store suffices. Release orders it after
+        // the `latest` store, so readers observing a version also observe its value.
+        self.version.store(self.version.load(Acquire) + 1, Release);
+        // Saturates after 584 years
+        #[cfg(feature = "std")]
+        self.modified.store(self.created.elapsed().as_nanos() as u64, Release);
+        instrument::publish();
+        #[cfg(feature = "std")]
+        self.notifier.notify();
+
+        // Notify observers while still holding the write-lock, so they see publishes in order
+        let published = self.latest_locked();
+        for observer in self.observers.lock().iter() {
+            observer(published);
+        }
+    }
+
+    /// Revert to the previous value, undoing the most recent publish. Blocks to acquire
+    /// write-lock.
+    ///
+    /// The value returned by [`read_previous`](SyncCow::read_previous) becomes the current value
+    /// again, without cloning it. It also stays the previous value, so a second rollback doesn't
+    /// undo the first one. The rolled back value is released. Like an edit, a rollback publishes
+    /// a new [`version`](SyncCow::version) and notifies observers.
+    ///
+    /// Readers may read the rolled back value before the rollback and the previous value after
+    /// it, so unlike with edits, later reads can return an older value.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(1);
+    /// cow.edit(|x| *x = 2);
+    /// cow.rollback();
+    /// assert_eq!(*cow.read(), 1);
+    /// ```
+    pub fn rollback(&self) {
+        let _lck = self.write_lock.lock();
+        let latest = self.latest.load(Acquire);
+        // Only publishes mark `previous`, and they hold the write-lock as well
+        let previous = self.previous.load(Relaxed);
+
+        // The previous buffer holds a committed value readers can read right away
+        self.latest.store(previous, Release);
+
+        // The rolled back value's buffer is neither the latest nor the previous one anymore, so
+        // its value can be replaced by the restored one like in `publish_locked`, which releases
+        // the rolled back value once late readers are done with it.
+        let buffer = &self.buffers[latest];
+        let old_ptr = buffer.swap(self.latest_locked().clone());
+        fence(SeqCst);
+        buffer.drain(self.backoff);
+        self.previous.store(latest, Release);
+        unsafe { buffer.release(old_ptr) };
+
+        self.announce_locked();
+    }
+
+    /// Get a mutable reference to the current value.
+    ///
+    /// As `&mut self` guarantees there are no concurrent readers or writers, the value is
+    /// mutated in place without going through [`edit`](SyncCow::edit). The value is only cloned
+    /// if an Arc returned by [`read`](SyncCow::read) is still alive, as in `Arc::make_mut`.
+    ///
+    /// Like `RwLock::get_mut`, this bypasses change tracking: the value is changed only after
+    /// the reference is returned, so there is no new value to announce. The
+    /// [`version`](SyncCow::version) and [`last_modified`](SyncCow::last_modified) stay the
+    /// same, and [`on_change`](SyncCow::on_change) callbacks and `watch` receivers are not
+    /// notified. Use `edit` for changes that must be observed.
+    ///
+    /// There is no in-place edit through `&self`, even when no Arc of the latest value is held
+    /// outside the SyncCow: readers access the latest value without taking the write-lock, and
+    /// [`peek`](SyncCow::peek) does so without holding an Arc at all. A reader may start at any
+    /// moment, so a shared SyncCow's latest value must never be mutated. Only `&mut self` rules
+    /// that out.
+    ///
+    /// ```
+    /// let mut cow = sync_cow::SyncCow::new(5);
+    /// *cow.get_mut() = 6;
+    /// assert_eq!(*cow.read(), 6);
+    /// ```
+    pub fn get_mut(&mut self) -> &mut T
+    where
+        T: Clone,
+    {
+        Arc::make_mut(self.get_arc_mut())
+    }
+
+    /// Get the latest Arc through exclusive access
+    fn get_arc_mut(&mut self) -> &mut Arc<T> {
+        let latest = &self.buffers[self.latest.load(Acquire)];
+        // `&mut self` guarantees no reader or writer is active, so we own the latest Box
+        unsafe { &mut *latest.ptr.load(Acquire) }
+    }
+
+    /// Consume the SyncCow and return the current value.
+    ///
+    /// The value is only cloned if an Arc returned by [`read`](SyncCow::read) is still alive.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(5);
+    /// cow.edit(|x| *x = 6);
+    /// assert_eq!(cow.into_inner(), 6);
+    /// ```
+    pub fn into_inner(mut self) -> T
+    where
+        T: Clone,
+    {
+        // Keep the latest Arc alive while dropping self frees both buffers
+        let latest = self.get_arc_mut().clone();
+        drop(self);
+        Arc::unwrap_or_clone(latest)
+    }
+
+    /// Consume the SyncCow and return the current value, or give the SyncCow back if that's
+    /// impossible without cloning.
+    ///
+    /// Unlike [`into_inner`](SyncCow::into_inner), this neither clones nor requires `T: Clone`.
+    /// If an Arc returned by [`read`](SyncCow::read) is still alive, the SyncCow is returned
+    /// unchanged in `Err`.
+    ///
+    /// In the rare case that a `Weak` returned by [`read_weak`](SyncCow::read_weak) is upgraded
+    /// concurrently, the returned SyncCow is rebuilt around the current value, keeping the
+    /// version, the modification time, the observers, the cloner, the clone observer, the
+    /// channel of `watch` and the configuration.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(5);
+    /// let held = cow.read();
+    /// let cow = cow.try_into_inner().unwrap_err();
+    /// drop(held);
+    /// assert_eq!(cow.try_into_inner().ok(), Some(5));
+    /// ```
+    // Like `Arc::try_unwrap`, the SyncCow is handed back by value on failure
+    #[allow(clippy::result_large_err)]
+    #[must_use = "on failure, the SyncCow is returned in `Err` and dropped if ignored"]
+    pub fn try_into_inner(mut self) -> Result<T, SyncCow<T>> {
+        let latest = self.get_arc_mut().clone();
+        // Buffers sharing the latest Arc, e.g. after `from_arc`, release their references with self.
+        // `&mut self` guarantees no reader or writer is active, so the pointers can be read.
+        let held = self
+            .buffers
+            .iter()
+            .filter(|buffer| Arc::ptr_eq(unsafe { &*buffer.ptr.load(Acquire) }, &latest))
+            .count();
+        if Arc::strong_count(&latest) > held + 1 {
+            return Err(self);
+        }
+
+        let (buffers, backoff, version) = (self.buffers.len(), self.backoff, self.version());
+        let alloc = self.buffers[0].alloc();
+        let observers = core::mem::take(&mut *self.observers.lock());
+        let cloner = self.cloner.take();
+        #[cfg(feature = "profiling")]
+        let clone_observer = self.clone_observer.lock().take();
+        #[cfg(feature = "async")]
+        let watch = self.watch.take();
+        #[cfg(feature = "std")]
+        let (created, modified) = (self.created, self.modified.load(Acquire));
+        drop(self);
+        Arc::try_unwrap(latest).map_err(|latest| {
+            let mut cow = SyncCow::from_buffers(
+                (0..buffers).map(|_| Buffer::new(latest.clone(), alloc.clone())).collect(),
+            );
+            cow.backoff = backoff;
+            cow.version = AtomicU64::new(version);
+            *cow.observers.lock() = observers;
+            cow.cloner = cloner;
+            #[cfg(feature = "profiling")]
+            {
+                *cow.clone_observer.lock() = clone_observer;
+            }
+            // The forwarder registered by `watch` is among the observers, so the sender must
+            // move along, or a later `watch` would register a second one
+            #[cfg(feature = "async")]
+            {
+                cow.watch = watch.map(std::sync::OnceLock::from).unwrap_or_default();
+            }
+            #[cfg(feature = "std")]
+            {
+                cow.created = created;
+                cow.modified = AtomicU64::new(modified);
+            }
+            cow
+        })
+    }
+
+    /// Consume the SyncCow and create a new one containing `f` applied to the current value.
+    ///
+    /// The value is recovered like in [`into_inner`](SyncCow::into_inner), and the result is
+    /// wrapped with [`new`](SyncCow::new). Callbacks registered with
+    /// [`on_change`](SyncCow::on_change) are dropped, as they take the old value type.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(String::from("moo"));
+    /// let len = cow.map_into(|s| s.len());
+    /// assert_eq!(*len.read(), 3);
+    /// ```
+    pub fn map_into<U, F>(self, f: F) -> SyncCow<U>
+    where
+        T: Clone,
+        U: Clone,
+        F: FnOnce(T) -> U,
+    {
+        SyncCow::new(f(self.into_inner()))
+    }
+
+    /// Pick the buffer to publish into. The caller must hold the write-lock.
+    ///
+    /// Prefers the oldest buffer without readers, so the writer doesn't have to wait for them.
+    /// If all buffers have readers, the oldest one is picked.
+    fn next_buffer_locked(&self, latest: usize) -> usize {
+        let count = self.buffers.len();
+        (1..count)
+            .map(|offset| (latest + offset) % count)
+            // Only a heuristic, `drain` in `publish_locked` synchronizes with the readers
+            .find(|&index| !self.buffers[index].has_readers())
+            .unwrap_or((latest + 1) % count)
+    }
+
+    /// Get the current value of the SyncCow as immutable std::sync::Arc.
+    ///
+    /// The `read` function will return the latest version of the SyncCow's value as an Arc.
+    /// This Arc contains an immutable state of the SyncCow's value; call `read()` again to obtain
+    /// an updated state after a writer has edited the value.
+    /// The reader can decide when to drop the Arc; the value will be dropped when a writer has
+    /// updated the value and no reader keeps an Arc of this value-state alive.
+    ///
+    /// The returned Arc is a consistent view of one published value, so fields read from it
+    /// always belong together. Reading the SyncCow again may return a newer value.
+    ///
+    /// Reads never go back in time: a read returns the value read before or a newer one, never
+    /// an older one. To guarantee this, a read retries if a writer published concurrently, which
+    /// is rare and never waits for the writer.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(5);
+    /// let val = cow.read();
+    /// assert_eq!(*val, 5);
+    /// cow.edit(|x| *x = 6);
+    /// assert_eq!(*val, 5);  // Arc keeps old value
+    /// assert_eq!(*cow.read(), 6); // Another read returns new value
+    /// ```
+    #[must_use]
+    pub fn read(&self) -> Arc<T> {
+        Arc::clone(&self.enter_latest())
+    }
+
+    /// Get the value published before the current one.
+    ///
+    /// "Previous" means the value the most recent publish replaced, e.g. for diffing the current
+    /// value against it. Before the first publish, it's the initial value. Like
+    /// [`read`](SyncCow::read), this is lock-less, but with concurrent writers the previous value
+    /// is a moving target: while a writer publishes, the current value may already be returned
+    /// as the previous one.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(1);
+    /// cow.edit(|x| *x = 2);
+    /// cow.edit(|x| *x = 3);
+    /// assert_eq!(*cow.read_previous(), 2);
+    /// ```
+    #[must_use]
+    pub fn read_previous(&self) -> Arc<T> {
+        Arc::clone(&self.enter_previous())
+    }
+
+    /// Read the current value and whether it was still current when the read completed.
+    ///
+    /// The flag is `true` if the [`version`](SyncCow::version) did not change while reading,
+    /// so the returned value was the latest one at the end of the read. It is `false` if a
+    /// writer published concurrently, in which case the value may already be superseded and
+    /// callers that need the latest value can read again.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(5);
+    /// let (value, fresh) = cow.read_with_freshness();
+    /// assert_eq!(*value, 5);
+    /// assert!(fresh);
+    /// ```
+    #[must_use]
+    pub fn read_with_freshness(&self) -> (Arc<T>, bool) {
+        let before = self.version();
+        let value = self.read();
+        (value, self.version() == before)
+    }
+
+    /// Read the current value with a single attempt, returning `None` if a writer published
+    /// meanwhile.
+    ///
+    /// [`read`](SyncCow::read) retries if a writer publishes while it loads the value, so under
+    /// a steady stream of writes it may loop. `try_read` never retries, blocks or spins: it
+    /// makes one attempt, and returns `None` if the version changed during it. It doesn't
+    /// allocate either, the returned value only costs an increment of the Arc's reference
+    /// count. This makes it suitable for real-time threads, where an occasional `None` is
+    /// preferable to an unbounded number of attempts.
+    ///
+    /// Note that dropping the returned Arc may free the value if the SyncCow has released it
+    /// meanwhile, so real-time threads should hand it elsewhere to be dropped. Unlike `read`,
+    /// `try_read` isn't counted by the `metrics` feature, as the recorder may lock or allocate.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(5);
+    /// assert_eq!(cow.try_read().as_deref(), Some(&5));
+    /// ```
+    #[must_use]
+    pub fn try_read(&self) -> Option<Arc<T>> {
+        let version = self.version();
+        let latest = self.latest.load(Acquire);
+        let reader = self.buffers[latest].enter();
+        // Like `enter_latest`, but give up instead of retrying if `latest` moved on
+        if self.latest.load(Acquire) != latest || self.version() != version {
+            return None;
+        }
+        Some(Arc::clone(&reader))
+    }
+
+    /// Read the current value as a [`Snapshot`], for code generic over owned and shared values.
+    ///
+    /// The snapshot shares the value like [`read`](SyncCow::read), and
+    /// [`into_owned`](Snapshot::into_owned) only clones it if it's still shared by then.
+    ///
+    /// A snapshot is one consistent view of the value: all fields read through it come from
+    /// the same published version, even if writers edit the SyncCow meanwhile. Read several
+    /// related fields from one snapshot rather than reading the SyncCow once per field (see
+    /// `examples/consistent_fields.rs`).
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(vec![1, 2]);
+    /// let snapshot = cow.snapshot();
+    /// assert_eq!(snapshot.len(), 2);
+    /// let owned: Vec<i32> = snapshot.into_owned();
+    /// assert_eq!(owned, vec![1, 2]);
+    /// ```
+    #[must_use]
+    pub fn snapshot(&self) -> Snapshot<T> {
+        Snapshot::Shared(self.read())
+    }
+
+    /// Read the current value into a guard that can be refreshed later.
+    ///
+    /// The guard holds an Arc like the one returned by [`read`](SyncCow::read) and derefs to the
+    /// value. Readers reading in a loop can keep the guard and call
+    /// [`refresh`](ReadGuard::refresh) whenever they want to pick up the latest value.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(5);
+    /// let mut guard = cow.read_guard();
+    /// cow.edit(|x| *x = 6);
+    /// assert_eq!(*guard, 5);
+    /// guard.refresh();
+    /// assert_eq!(*guard, 6);
+    /// ```
+    #[must_use]
+    pub fn read_guard(&self) -> ReadGuard<'_, T> {
+        ReadGuard::new(self)
+    }
+
+    /// Read a part of the current value, e.g. one of its fields.
+    ///
+    /// Like [`read`](SyncCow::read), but the returned handle derefs to the part of the value
+    /// returned by `f`. The handle holds the Arc of the whole value, so the value stays alive as
+    /// long as the handle does.
+    ///
+    /// ```
+    /// #[derive(Clone)]
+    /// struct User { name: String, age: u8 }
+    ///
+    /// let cow = sync_cow::SyncCow::new(User { name: "Moo".into(), age: 3 });
+    /// let name = cow.read_map(|user| &user.name);
+    /// assert_eq!(*name, "Moo");
+    /// ```
+    #[must_use]
+    pub fn read_map<U, F>(&self, f: F) -> MappedArc<T, U>
+    where
+        U: ?Sized,
+        F: for<'a> FnOnce(&'a T) -> &'a U,
+    {
+        MappedArc::new(self.read(), f)
+    }
+
+    /// Compute a value from the current value and wrap it in a new Arc.
+    ///
+    /// Unlike [`read_map`](SyncCow::read_map), which keeps the whole value alive to borrow a
+    /// part of it, the returned Arc is independent of the SyncCow and its values. `f` runs like
+    /// a [`peek`](SyncCow::peek) closure, so it should return quickly.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(vec![1, 2, 3]);
+    /// let sum = cow.map_read(|v| v.iter().sum::<i32>());
+    /// assert_eq!(*sum, 6);
+    /// ```
+    #[must_use]
+    pub fn map_read<U, F>(&self, f: F) -> Arc<U>
+    where
+        F: FnOnce(&T) -> U,
+    {
+        Arc::new(self.peek(f))
+    }
+
+    /// Run `f` with a reference to the current value, without cloning the Arc.
+    ///
+    /// This avoids the reference count update of [`read`](SyncCow::read) for short inspections
+    /// of the value. While `f` runs, the SyncCow's buffer holding the value is marked as being
+    /// read, and a writer publishing into that buffer waits until `f` returns. So `f` should
+    /// return quickly and must not block, and in particular must not edit the SyncCow. If `f`
+    /// panics, the buffer is released while unwinding, so writers don't wait for it forever.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(vec![1, 2, 3]);
+    /// assert_eq!(cow.peek(|v| v.len()), 3);
+    /// ```
+    pub fn peek<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        f(self.enter_latest().as_ref())
+    }
+
+    /// Call `f` `iters` times with the current value, entering the SyncCow only once.
+    ///
+    /// For hot polling loops, this amortizes the reader handshake of [`peek`](SyncCow::peek)
+    /// over many logical reads. All iterations see the same value, even if writers publish
+    /// meanwhile, so it gets more outdated the longer the loop runs. Like during a `peek`, a
+    /// writer publishing into the buffer holding the value waits until the loop is done.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(vec![1, 2, 3]);
+    /// let mut sum = 0;
+    /// cow.read_repeated(10, |v| sum += v.iter().sum::<i32>());
+    /// assert_eq!(sum, 60);
+    /// ```
+    pub fn read_repeated(&self, iters: usize, mut f: impl FnMut(&T)) {
+        self.peek(|value| (0..iters).for_each(|_| f(value)));
+    }
+
+    /// Borrow the current value through a guard, without cloning the Arc.
+    ///
+    /// The guard-based counterpart of [`peek`](SyncCow::peek): the guard derefs to the value and
+    /// marks the SyncCow's buffer holding it as being read until it's dropped. Its lifetime is
+    /// tied to the SyncCow, so it can't outlive it. Use [`read`](SyncCow::read) to keep the
+    /// value for longer.
+    ///
+    /// Like `peek`, holding the guard delays writers: once a writer publishes into the borrowed
+    /// buffer, which happens after one further publish with the default two buffers, it waits
+    /// until the guard is dropped. So the guard should be dropped quickly, and the same thread
+    /// must not edit the SyncCow while holding it, as that may wait for the guard forever.
+    ///
+    /// Leaking a guard, e.g. through `mem::forget`, is safe, but like a guard held indefinitely
+    /// it marks its buffer as read forever: every writer publishing into that buffer blocks
+    /// forever. Dropping the SyncCow afterwards is fine and frees all values.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(vec![1, 2, 3]);
+    /// let values = cow.borrow();
+    /// assert_eq!(values.len(), 3);
+    /// drop(values);
+    /// cow.edit(|v| v.clear());
+    /// ```
+    #[must_use]
+    pub fn borrow(&self) -> ReadRef<'_, T> {
+        ReadRef::new(self.enter_latest())
+    }
+
+    /// Inspect the current value by reference. The recommended way to look at the value.
+    ///
+    /// Most reads only compute something from the value, e.g. look up an entry or format it.
+    /// `with` does so by borrowing the value for the duration of `f`, without cloning the Arc
+    /// like [`read`](SyncCow::read). Use `read` to keep the value beyond the closure. This is
+    /// the same as [`peek`](SyncCow::peek), with the same restrictions on `f`: it should return
+    /// quickly and must not edit the SyncCow.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(vec![1, 2, 3]);
+    /// let sum: i32 = cow.with(|v| v.iter().sum());
+    /// assert_eq!(sum, 6);
+    /// ```
+    pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        self.peek(f)
+    }
+
+    /// Run `f` with a raw pointer to the current value, e.g. to hand it to C code.
+    ///
+    /// Like [`peek`](SyncCow::peek), the buffer holding the value is marked as being read while
+    /// `f` runs, so writers publishing into it wait and the pointee stays alive. The same
+    /// restrictions on `f` apply.
+    ///
+    /// # Safety
+    ///
+    /// The pointer is only valid until `f` returns. It must not be stored or used afterwards,
+    /// neither by `f` nor by code `f` passed it to. Concurrent writers may already have published
+    /// a newer value while `f` runs, and the pointee must not be written through the pointer.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(5u32);
+    /// let value = unsafe { cow.with_current_ptr(|ptr| *ptr) };
+    /// assert_eq!(value, 5);
+    /// ```
+    pub unsafe fn with_current_ptr<R>(&self, f: impl FnOnce(*const T) -> R) -> R {
+        let reader = self.enter_latest();
+        f(Arc::as_ptr(&reader))
+    }
+
+    /// Clone the current value into `buf`, reusing its allocations.
+    ///
+    /// Uses `Clone::clone_from`, which e.g. for a `Vec` reuses `buf`'s memory if its capacity
+    /// suffices. Readers processing the value repeatedly can keep their own buffer this way, giving
+    /// up the shared ownership of [`read`](SyncCow::read) in exchange for not allocating.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(vec![1, 2, 3]);
+    /// let mut buf = Vec::new();
+    /// cow.read_into(&mut buf);
+    /// assert_eq!(buf, vec![1, 2, 3]);
+    /// ```
+    pub fn read_into(&self, buf: &mut T)
+    where
+        T: Clone,
+    {
+        self.peek(|value| buf.clone_from(value))
+    }
+
+    /// Get a weak reference to the current value.
+    ///
+    /// The Weak does not keep the value alive. Besides the readers' Arcs, the SyncCow itself
+    /// holds a strong reference to each value for as long as it stays in one of its buffers, i.e.
+    /// until enough newer values have been published to reuse that buffer. After that, and once
+    /// all Arcs handed out by [`read`](SyncCow::read) are dropped, `upgrade` returns `None`.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(5);
+    /// let weak = cow.read_weak();
+    /// assert_eq!(*weak.upgrade().unwrap(), 5);
+    /// ```
+    #[must_use]
+    pub fn read_weak(&self) -> Weak<T> {
+        Arc::downgrade(&self.enter_latest())
+    }
+
+    /// Get a clone of the current value.
+    ///
+    /// Where [`read`](SyncCow::read) shares ownership of the value through an Arc, this clones
+    /// the value itself. For small values, especially `Copy` ones, that is cheaper than updating
+    /// the Arc's reference count, and the caller gets a plain `T`. For large values, prefer `read`.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(5);
+    /// let val: i32 = cow.get();
+    /// assert_eq!(val, 5);
+    /// ```
+    #[must_use]
+    pub fn get(&self) -> T
+    where
+        T: Clone,
+    {
+        self.peek(T::clone)
+    }
+
+    /// Announce a reader of the latest buffer, keeping its value valid while the reader lives
+    fn enter_latest(&self) -> Reader<'_, T> {
+        instrument::read();
+        loop {
+            // Acquire pairs with the writer's Release store, making the published pointer visible
+            let latest = self.latest.load(Acquire);
+            // We want to read whatever has been updated last. Entering the buffer notifies the
+            // writer, so it waits before releasing the value.
+            let reader = self.buffers[latest].enter();
+
+            // By now `latest` may be outdated, and a writer may already be publishing into its
+            // buffer. The pointer is still safe to use, but it may be the writer's pending value,
+            // while the next read could return the older value still marked as latest. If
+            // `latest` is unchanged, the value was the latest one at the time it was loaded.
+            if self.latest.load(Acquire) == latest {
+                return reader;
+            }
+        }
+    }
+
+    /// Announce a reader of the buffer holding the previous value, see `read_previous`
+    fn enter_previous(&self) -> Reader<'_, T> {
+        instrument::read();
+        loop {
+            let previous = self.previous.load(Acquire);
+            // While a writer replaces the previous value, the latest one becomes the previous
+            // one once the writer is done, so that's read instead
+            let index = match previous {
+                NO_PREVIOUS => self.latest.load(Acquire),
+                previous => previous,
+            };
+            let reader = self.buffers[index].enter();
+
+            // Like in `enter_latest`, the buffer may have been reused meanwhile. A writer
+            // publishing into it marks `previous` first, so an unchanged `previous` means we
+            // didn't load a pending value. The mark isn't unique, so for a marked read we check
+            // that the buffer is still the latest before checking the mark, which then belongs
+            // to a writer publishing after the latest value, not into its buffer.
+            let unchanged = match previous {
+                NO_PREVIOUS => {
+                    self.latest.load(Acquire) == index
+                        && self.previous.load(Acquire) == NO_PREVIOUS
+                }
+                previous => self.previous.load(Acquire) == previous,
+            };
+            if unchanged {
+                return reader;
+            }
+        }
+    }
+
+    /// Read the values of all buffers and the index of the latest one, for white-box tests.
+    ///
+    /// Each buffer is entered like by a reader, so this doesn't race with writers draining it.
+    #[cfg(all(test, feature = "std", not(loom)))]
+    pub(crate) fn debug_buffers(&self) -> (Vec<Arc<T>>, usize) {
+        let latest = self.latest.load(Acquire);
+        let values = self.buffers.iter().map(|buffer| Arc::clone(&buffer.enter())).collect();
+        (values, latest)
+    }
+
+    /// Register a callback invoked with the new value whenever a writer publishes one.
+    ///
+    /// Callbacks run on the writer's thread after the new value has been published, but before
+    /// the write-lock is released, so they observe publishes in order. They delay the writer and
+    /// all other writers waiting for the write-lock, so they should be cheap. Callbacks must not
+    /// edit the SyncCow or register further callbacks, as that would deadlock (a reentrant edit
+    /// panics instead, see [`edit`](SyncCow::edit)).
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(5);
+    /// cow.on_change(|x| println!("Cow is now {}", x));
+    /// cow.edit(|x| *x = 6);
+    /// ```
+    pub fn on_change(&self, f: impl Fn(&Arc<T>) + Send + Sync + 'static) {
+        self.observers.lock().push(Box::new(f));
+    }
+
+    /// Set a callback invoked with the duration of each clone of the value made by an edit.
+    ///
+    /// Edits clone the latest value, which dominates their cost for large values. Timing the
+    /// clones shows whether that's the case, and whether a SyncCow is a good fit for the value.
+    /// The callback runs on the writer's thread while holding the write-lock, so it should be
+    /// cheap. Setting a callback replaces the previous one. Without a callback, clones are not
+    /// timed.
+    ///
+    /// Only available with the `profiling` feature.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(vec![0u8; 1024]);
+    /// cow.set_clone_observer(|duration| println!("Cloning took {:?}", duration));
+    /// cow.edit(|v| v[0] = 1);
+    /// ```
+    #[cfg(feature = "profiling")]
+    pub fn set_clone_observer(&self, f: impl Fn(std::time::Duration) + Send + Sync + 'static) {
+        *self.clone_observer.lock() = Some(Box::new(f));
+    }
+
+    /// Get the version of the SyncCow's value.
+    ///
+    /// The version starts at 0 and is incremented by 1 whenever a writer publishes a new value.
+    /// Comparing versions is a cheap way to detect changes without reading the value.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(5);
+    /// assert_eq!(cow.version(), 0);
+    /// cow.edit(|x| *x = 6);
+    /// assert_eq!(cow.version(), 1);
+    /// ```
+    #[must_use]
+    pub fn version(&self) -> u64 {
+        self.version.load(Acquire)
+    }
+
+    /// Get the time of the most recent publish, or of the creation of the SyncCow if nothing
+    /// has been published yet.
+    ///
+    /// Like [`version`](SyncCow::version), but for cache layers deciding by age rather than by
+    /// version. Only available with the `std` feature.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(5);
+    /// let created = cow.last_modified();
+    /// cow.edit(|x| *x = 6);
+    /// assert!(cow.last_modified() > created);
+    /// ```
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn last_modified(&self) -> std::time::Instant {
+        self.created + std::time::Duration::from_nanos(self.modified.load(Acquire))
+    }
+
+    /// Block until the version is at least `at_least`, then read the current value.
+    ///
+    /// Waiting threads are parked and woken whenever a writer publishes, instead of polling
+    /// [`version`](SyncCow::version). The returned value is at least as new as version
+    /// `at_least`, and may be newer if writers keep publishing. Returns immediately if the
+    /// version already suffices.
+    ///
+    /// Only available with the `std` feature.
+    ///
+    /// ```
+    /// let cow = std::sync::Arc::new(sync_cow::SyncCow::new(5));
+    /// let writer = {
+    ///     let cow = cow.clone();
+    ///     std::thread::spawn(move || cow.edit(|x| *x = 6))
+    /// };
+    /// assert_eq!(*cow.wait_for_version(1), 6);
+    /// writer.join().unwrap();
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn wait_for_version(&self, at_least: u64) -> Arc<T> {
+        self.notifier.wait_until(|| self.version() >= at_least);
+        self.read()
+    }
+
+    /// Iterate over the values writers publish from now on, blocking until each one arrives.
+    ///
+    /// A simple way to watch a SyncCow without polling. The iterator starts at the current
+    /// version and yields the value after each publish. Slow consumers may miss intermediate
+    /// values: if several values are published while the consumer is busy, only the latest one
+    /// is yielded. See [`ChangeIter`].
+    ///
+    /// Only available with the `std` feature.
+    ///
+    /// ```
+    /// let cow = std::sync::Arc::new(sync_cow::SyncCow::new(5));
+    /// let mut changes = cow.changes();
+    /// let writer = {
+    ///     let cow = cow.clone();
+    ///     std::thread::spawn(move || cow.edit(|x| *x = 6))
+    /// };
+    /// assert_eq!(*changes.next().unwrap(), 6);
+    /// writer.join().unwrap();
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn changes(&self) -> ChangeIter<'_, T> {
+        ChangeIter::new(self)
+    }
+
+    /// Get the number of readers currently accessing the SyncCow's buffers, for diagnostics.
+    ///
+    /// The count includes [`read`](SyncCow::read) calls in progress and open
+    /// [`peek`](SyncCow::peek) closures. It is only approximate: readers come and go while the
+    /// buffers are summed up, so the result is outdated as soon as it's returned.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(5);
+    /// assert_eq!(cow.active_readers(), 0);
+    /// cow.peek(|_| assert_eq!(cow.active_readers(), 1));
+    /// ```
+    #[must_use]
+    pub fn active_readers(&self) -> usize {
+        self.buffers.iter().map(Buffer::readers).sum()
+    }
+
+    /// Get the number of Arcs of the current value held outside the SyncCow, for diagnostics.
+    ///
+    /// These are Arcs returned by [`read`](SyncCow::read) and similar methods that are still
+    /// alive, e.g. for cache-eviction heuristics deciding whether the current value is in use.
+    /// Held Arcs don't delay writers, they only keep the value alive after it's replaced, while
+    /// [`active_readers`](SyncCow::active_readers) counts the readers a writer may wait for.
+    ///
+    /// Like `active_readers`, the count is only approximate: readers clone and drop Arcs and
+    /// writers replace the value while it's counted.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(5);
+    /// assert_eq!(cow.current_strong_count(), 0);
+    /// let held = cow.read();
+    /// assert_eq!(cow.current_strong_count(), 1);
+    /// ```
+    #[must_use]
+    pub fn current_strong_count(&self) -> usize {
+        let latest = self.enter_latest();
+        // Buffers sharing the latest Arc, e.g. after `from_arc`, hold internal references too
+        let held = self
+            .buffers
+            .iter()
+            .filter(|buffer| Arc::ptr_eq(&buffer.enter(), &latest))
+            .count();
+        Arc::strong_count(&latest).saturating_sub(held)
+    }
+
+    /// Read the current value only if its version differs from `last`.
+    ///
+    /// Returns `None` if the version still equals `last`, otherwise the current value together
+    /// with the version observed before reading it. If a writer publishes while reading, the
+    /// returned value may already be newer than the returned version; the next call then returns
+    /// the value again rather than missing the change.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(5);
+    /// let (val, version) = cow.read_if_changed(u64::MAX).unwrap();
+    /// assert_eq!(*val, 5);
+    /// assert!(cow.read_if_changed(version).is_none());
+    /// cow.edit(|x| *x = 6);
+    /// assert_eq!(*cow.read_if_changed(version).unwrap().0, 6);
+    /// ```
+    #[must_use]
+    pub fn read_if_changed(&self, last: u64) -> Option<(Arc<T>, u64)> {
+        let version = self.version();
+        if version == last {
+            return None;
+        }
+        Some((self.read(), version))
+    }
+
+    /// Read the current value only if it was published after `since`.
+    ///
+    /// Returns `None` if [`last_modified`](SyncCow::last_modified) is not later than `since`.
+    /// Like [`read_if_changed`](SyncCow::read_if_changed), the returned value may be even newer
+    /// if a writer publishes meanwhile. Only available with the `std` feature.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(5);
+    /// let fetched = std::time::Instant::now();
+    /// assert!(cow.read_if_modified_since(fetched).is_none());
+    /// cow.edit(|x| *x = 6);
+    /// assert_eq!(*cow.read_if_modified_since(fetched).unwrap(), 6);
+    /// ```
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn read_if_modified_since(&self, since: std::time::Instant) -> Option<Arc<T>> {
+        (self.last_modified() > since).then(|| self.read())
+    }
+
+    /// Create a new SyncCow containing `obj`.
+    ///
+    /// As the SyncCow stores two copies of its value, `obj` is cloned once.
+    ///
+    /// A SyncCow can also be created with `From`/`Into`:
+    /// ```
+    /// let cow: sync_cow::SyncCow<_> = 5.into();
+    /// assert_eq!(*cow.read(), 5);
+    /// ```
+    ///
+    /// `new` allocates the SyncCow's buffers and the Arcs holding the value, which is impossible
+    /// in a `const fn`, even for zero-sized values. To store a SyncCow in a `static`, create it
+    /// lazily, e.g. with `std::sync::LazyLock` (see `examples/global_config.rs`):
+    /// ```
+    /// use std::sync::LazyLock;
+    /// static LEVEL: LazyLock<sync_cow::SyncCow<u8>> = LazyLock::new(|| sync_cow::SyncCow::new(1));
+    /// LEVEL.set(2);
+    /// assert_eq!(*LEVEL.read(), 2);
+    /// ```
+    pub fn new(obj: T) -> SyncCow<T>
+    where
+        T: Clone,
+    {
+        SyncCow::with_buffers(obj, 2)
+    }
+
+    /// Create a builder for a SyncCow with non-default configuration.
+    ///
+    /// [`new`](SyncCow::new) is equivalent to `SyncCow::builder().build(obj)`.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::builder().buffers(4).build(5);
+    /// assert_eq!(*cow.read(), 5);
+    /// ```
+    pub fn builder() -> SyncCowBuilder<T>
+    where
+        T: Clone,
+    {
+        SyncCowBuilder::new()
+    }
+
+    /// Create a new SyncCow containing `obj`, wrapped in an Arc for sharing it between threads.
+    ///
+    /// Shorthand for `Arc::new(SyncCow::new(obj))`. Each clone of the Arc accesses the same
+    /// SyncCow, so edits through one clone are read through all others.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::shared(5);
+    /// let writer = {
+    ///     let cow = cow.clone();
+    ///     std::thread::spawn(move || cow.edit(|x| *x = 6))
+    /// };
+    /// writer.join().unwrap();
+    /// assert_eq!(*cow.read(), 6);
+    /// ```
+    pub fn shared(obj: T) -> Arc<SyncCow<T>>
+    where
+        T: Clone,
+    {
+        Arc::new(SyncCow::new(obj))
+    }
+
+    /// Create a new SyncCow containing `obj`, storing it in `buffers` buffers.
+    ///
+    /// A writer publishes into one of the buffers not holding the latest value, and has to wait
+    /// for readers still cloning the value previously stored there. With more than two buffers
+    /// the writer can pick one without readers, which reduces writer stalls under heavy reading
+    /// at the cost of keeping more values alive. `obj` is cloned `buffers - 1` times.
+    ///
+    /// Panics if `buffers` is less than 2.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::with_buffers(5, 4);
+    /// cow.edit(|x| *x = 6);
+    /// assert_eq!(*cow.read(), 6);
+    /// ```
+    pub fn with_buffers(obj: T, buffers: usize) -> SyncCow<T>
+    where
+        T: Clone,
+    {
+        SyncCow::with_buffers_in(obj, buffers, BoxAlloc::global())
+    }
+
+    /// Create a new SyncCow containing `obj`, allocating the Boxes that hold its published Arcs
+    /// with `alloc`.
+    ///
+    /// Each buffer stores its value as a `Box<Arc<T>>`, allocated whenever a value is published
+    /// and freed once it's replaced and drained. These Boxes are allocated with `alloc`, e.g. to
+    /// place the SyncCow's bookkeeping in an arena, while the values and their Arcs, the reader
+    /// counts and the SyncCow itself still use the global allocator. Clones of the SyncCow share
+    /// `alloc`.
+    ///
+    /// Only available with the `allocator_api` feature, which requires a nightly compiler.
+    ///
+    /// ```
+    /// #![feature(allocator_api)]
+    /// let cow = sync_cow::SyncCow::new_in(5, std::alloc::System);
+    /// cow.edit(|x| *x = 6);
+    /// assert_eq!(*cow.read(), 6);
+    /// ```
+    #[cfg(feature = "allocator_api")]
+    pub fn new_in<A>(obj: T, alloc: A) -> SyncCow<T>
+    where
+        T: Clone,
+        A: core::alloc::Allocator + Send + Sync + 'static,
+    {
+        SyncCow::with_buffers_in(obj, 2, BoxAlloc::new(alloc))
+    }
+
+    fn with_buffers_in(obj: T, buffers: usize, alloc: BoxAlloc) -> SyncCow<T>
+    where
+        T: Clone,
+    {
+        assert!(buffers >= 2, "SyncCow needs at least 2 buffers");
+        // Each buffer needs its own value, so `obj` is cloned for all but the last one
+        let mut values: Vec<Buffer<T>> = (1..buffers)
+            .map(|_| Buffer::new(Arc::new(obj.clone()), alloc.clone()))
+            .collect();
+        values.push(Buffer::new(Arc::new(obj), alloc));
+        let mut cow = SyncCow::from_buffers(values);
+        cow.cloner = clone_cloner();
+        cow
+    }
+
+    /// Create a new SyncCow containing a collection built from the items of `iter`.
+    ///
+    /// Like [`new`](SyncCow::new) with `Iterator::collect`, the collection is cloned once.
+    ///
+    /// ```
+    /// let cow: sync_cow::SyncCow<Vec<_>> = sync_cow::SyncCow::collect(1..=3);
+    /// assert_eq!(*cow.read(), [1, 2, 3]);
+    /// ```
+    pub fn collect<I>(iter: I) -> SyncCow<T>
+    where
+        T: Clone + FromIterator<I::Item>,
+        I: IntoIterator,
+    {
+        SyncCow::new(iter.into_iter().collect())
+    }
+
+    /// Create a new SyncCow with values built by `factory` instead of cloning a single value.
+    ///
+    /// `factory` is called exactly twice, once for each of the SyncCow's two buffers, and both
+    /// calls must produce equal values. This helps when `T` is cheaper to build fresh than to
+    /// clone, or when cloning has side effects.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new_with(|| vec![0u8; 1024]);
+    /// assert_eq!(cow.read().len(), 1024);
+    /// ```
+    pub fn new_with<F>(mut factory: F) -> SyncCow<T>
+    where
+        T: Clone,
+        F: FnMut() -> T,
+    {
+        let buffers = (0..2).map(|_| Buffer::new(Arc::new(factory()), BoxAlloc::global()));
+        let mut cow = SyncCow::from_buffers(buffers.collect());
+        cow.cloner = clone_cloner();
+        cow
+    }
+
+    /// Create a new SyncCow publishing `value` without cloning it.
+    ///
+    /// Both buffers share `value`, so `T` does not need to be `Clone`. Such a SyncCow is
+    /// read-mostly: it can be read and replaced as a whole with [`set`](SyncCow::set),
+    /// [`replace`](SyncCow::replace) or [`update`](SyncCow::update), but it has no cloner, so
+    /// the [`edit`](SyncCow::edit) family, which clones the latest value, panics. Use
+    /// [`new_shared_initial`](SyncCow::new_shared_initial) or
+    /// [`with_cloner`](SyncCow::with_cloner) to edit a shared initial value.
+    ///
+    /// ```
+    /// use std::sync::Arc;
+    ///
+    /// struct Config {
+    ///     name: String,
+    /// }
+    ///
+    /// let cow = sync_cow::SyncCow::from_arc(Arc::new(Config { name: "a".into() }));
+    /// cow.set(Config { name: "b".into() });
+    /// assert_eq!(cow.read().name, "b");
+    /// ```
+    pub fn from_arc(value: Arc<T>) -> SyncCow<T> {
+        let buffers = (0..2).map(|_| Buffer::new(value.clone(), BoxAlloc::global()));
+        SyncCow::from_buffers(buffers.collect())
+    }
+
+    /// Create a new SyncCow whose edits clone the value with `cloner` instead of `T::clone`.
+    ///
+    /// Useful if `T` has several ways of cloning, e.g. to leave out caches or reset bookkeeping
+    /// fields in the copy an edit works on. All methods cloning the value for an edit use
+    /// `cloner`, including [`edit_if`](SyncCow::edit_if) and the `try_edit` variants, while
+    /// reading methods like [`get`](SyncCow::get) need `T: Clone`. `initial` is not cloned,
+    /// both buffers share it like with [`new_shared_initial`](SyncCow::new_shared_initial).
+    ///
+    /// `T` doesn't need to implement `Clone` to be edited, `cloner` is used instead.
+    ///
+    /// ```
+    /// struct Counter {
+    ///     count: u32,
+    ///     cached_label: Option<String>,
+    /// }
+    ///
+    /// let cow = sync_cow::SyncCow::with_cloner(Counter { count: 0, cached_label: None }, |c| {
+    ///     Counter { count: c.count, cached_label: None }
+    /// });
+    /// cow.edit(|c| {
+    ///     c.count += 1;
+    ///     c.cached_label = Some(c.count.to_string());
+    /// });
+    /// cow.edit(|c| assert!(c.cached_label.is_none()));
+    /// ```
+    pub fn with_cloner(initial: T, cloner: impl Fn(&T) -> T + Send + Sync + 'static) -> SyncCow<T> {
+        let mut cow = SyncCow::from_arc(Arc::new(initial));
+        cow.cloner = Some(Cloner::Custom(Arc::new(cloner)));
+        cow
+    }
+
+    /// Create a new SyncCow containing `obj` without cloning it.
+    ///
+    /// Unlike [`new`](SyncCow::new), which stores a deep clone of `obj` in the second buffer,
+    /// both buffers share a single Arc, like with [`from_arc`](SyncCow::from_arc). Construction
+    /// then costs no clone of `T`, which helps for values that are expensive to clone. Edits
+    /// clone the latest value into a new Arc anyway, so sharing the initial one is safe.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new_shared_initial(vec![0u8; 1024]);
+    /// cow.edit(|v| v[0] = 1);
+    /// assert_eq!(cow.read()[0], 1);
+    /// ```
+    pub fn new_shared_initial(obj: T) -> SyncCow<T>
+    where
+        T: Clone,
+    {
+        let mut cow = SyncCow::from_arc(Arc::new(obj));
+        cow.cloner = clone_cloner();
+        cow
+    }
+
+    fn from_buffers(buffers: Vec<Buffer<T>>) -> SyncCow<T> {
+        SyncCow {
+            // moooo
+            latest: AtomicUsize::new(0),
+            previous: AtomicUsize::new(1),
+            version: AtomicU64::new(0),
+            #[cfg(feature = "std")]
+            created: std::time::Instant::now(),
+            #[cfg(feature = "std")]
+            modified: AtomicU64::new(0),
+            write_lock: WriteLock::new(),
+            observers: Mutex::new(Vec::new()),
+            cloner: None,
+            #[cfg(feature = "profiling")]
+            clone_observer: Mutex::new(None),
+            #[cfg(feature = "std")]
+            notifier: Notifier::new(),
+            #[cfg(feature = "async")]
+            async_write_lock: tokio::sync::Mutex::new(()),
+            #[cfg(feature = "async")]
+            watch: std::sync::OnceLock::new(),
+            buffers: buffers.into_boxed_slice(),
+            backoff: Backoff::default(),
+        }
+    }
+}
+
+impl<T: Default> SyncCow<T> {
+    /// Reset the contents of the SyncCow to `T::default()` and return the previous value.
+    /// Blocks to acquire write-lock.
+    ///
+    /// Works like [`replace`](SyncCow::replace) with the default value, readers either read the
+    /// previous value or the default value.
+    ///
+    /// ```
+    /// let cow = sync_cow::SyncCow::new(vec![1, 2]);
+    /// let events = cow.take();
+    /// assert_eq!(*events, vec![1, 2]);
+    /// assert!(cow.read().is_empty());
+    /// ```
+    pub fn take(&self) -> Arc<T> {
+        self.replace(T::default())
+    }
+}
+
+/// Creates an independent SyncCow holding a snapshot of the current value. Edits to either
+/// SyncCow are not visible in the other.
+///
+/// The value is cloned once and shared by all buffers of the new SyncCow, like with
+/// [`new_shared_initial`](SyncCow::new_shared_initial). The configuration is kept: the number of
+/// buffers, the backoff and the cloner set with [`with_cloner`](SyncCow::with_cloner). The
+/// version, observers and watch channels are not cloned, the new SyncCow starts afresh.
+impl<T: Clone> Clone for SyncCow<T> {
+    fn clone(&self) -> Self {
+        let value = Arc::new(self.peek(T::clone));
+        let alloc = self.buffers[0].alloc();
+        let buffers = (0..self.buffers.len()).map(|_| Buffer::new(value.clone(), alloc.clone()));
+        let mut cow = SyncCow::from_buffers(buffers.collect());
+        cow.cloner = self.cloner.clone();
+        cow.backoff = self.backoff;
+        cow
+    }
+}
+
+impl<T: Clone> From<T> for SyncCow<T> {
+    fn from(obj: T) -> Self {
+        SyncCow::new(obj)
+    }
+}
+
+/// Compares point-in-time snapshots of both values, taken with [`read`](SyncCow::read).
+/// If either SyncCow is edited concurrently the result may be outdated immediately, so this is
+/// mainly useful in single-threaded or quiescent contexts, e.g. tests and assertions.
+impl<T: PartialEq> PartialEq for SyncCow<T> {
+    fn eq(&self, other: &Self) -> bool {
+        *self.read() == *other.read()
+    }
+}
+
+/// Compares a point-in-time snapshot of the value, taken with [`read`](SyncCow::read), to `other`.
+///
+/// ```
+/// let cow = sync_cow::SyncCow::new(5);
+/// assert!(cow == 5);
+/// ```
+impl<T: PartialEq> PartialEq<T> for SyncCow<T> {
+    fn eq(&self, other: &T) -> bool {
+        *self.read() == *other
+    }
+}
+
+impl<T: Eq> Eq for SyncCow<T> {}
+
+/// Hashes a point-in-time snapshot of the value, taken with [`read`](SyncCow::read).
+/// Like [`PartialEq`], this is racy if the SyncCow is edited concurrently, and a SyncCow used as
+/// key in a hash-based collection must not be edited while it's in there.
+///
+/// ```
+/// # use std::collections::HashSet;
+/// let mut set = HashSet::new();
+/// set.insert(sync_cow::SyncCow::new(5));
+/// assert!(set.contains(&sync_cow::SyncCow::new(5)));
+/// ```
+impl<T: Hash> Hash for SyncCow<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.read().hash(state)
+    }
+}
+
+impl<T: Clone + Default> Default for SyncCow<T> {
+    fn default() -> Self {
+        SyncCow::new(T::default())
+    }
+}
+
+/// Formats a point-in-time snapshot of the value, taken with a lock-less [`read`](SyncCow::read).
+/// The printed value may already be stale if a writer edits the SyncCow concurrently.
+impl<T: fmt::Debug> fmt::Debug for SyncCow<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SyncCow")
+            .field("value", &*self.read())
+            .field("latest", &self.latest.load(Acquire))
+            .finish()
+    }
+}
+
+// `AtomicPtr` is `Send + Sync` for any pointee, so the auto traits would be implemented even for
+// a `T` that must not cross threads. Every value is handed out as an `Arc<T>`, possibly to another
+// thread, and dropped on whichever thread releases it last, which requires `T: Send + Sync` just
+// like `Arc<T>` itself does.
+unsafe impl<T: Send + Sync> Send for SyncCow<T> {}
+unsafe impl<T: Send + Sync> Sync for SyncCow<T> {}
@@ -0,0 +1,146 @@
+//! Exhaustive concurrency tests using loom. Run with
+//! `RUSTFLAGS="--cfg loom" cargo test --release --lib loom`.
+//! Set `LOOM_MAX_PREEMPTIONS=3` to bound the exploration for a quick run.
+
+use crate::SyncCow;
+use loom::cell::UnsafeCell;
+use loom::sync::Arc;
+use loom::thread;
+
+/// Value whose contents are tracked by loom, so reading it without a happens-before relation to
+/// the writer that initialized it is reported as a causality violation.
+struct Tracked(UnsafeCell<usize>);
+
+impl Tracked {
+    fn new(value: usize) -> Tracked {
+        Tracked(UnsafeCell::new(value))
+    }
+
+    fn get(&self) -> usize {
+        self.0.with(|v| unsafe { *v })
+    }
+
+    fn set(&mut self, value: usize) {
+        self.0.with_mut(|v| unsafe { *v = value })
+    }
+}
+
+impl Clone for Tracked {
+    fn clone(&self) -> Self {
+        Tracked::new(self.get())
+    }
+}
+
+#[test]
+fn loom_one_writer_two_readers() {
+    loom::model(|| {
+        let cow = Arc::new(SyncCow::new(Tracked::new(1)));
+
+        let readers: Vec<_> = (0..2)
+            .map(|_| {
+                let cow = cow.clone();
+                thread::spawn(move || {
+                    let first = cow.read().get();
+                    let second = cow.read().get();
+                    assert!(first == 1 || first == 2, "Read torn value {}", first);
+                    assert!(second == 1 || second == 2, "Read torn value {}", second);
+                })
+            })
+            .collect();
+
+        cow.edit(|v| v.set(2));
+        assert_eq!(cow.read().get(), 2);
+
+        for reader in readers {
+            reader.join().unwrap();
+        }
+    });
+}
+
+#[test]
+fn loom_two_writers_one_reader() {
+    loom::model(|| {
+        let cow = Arc::new(SyncCow::new(Tracked::new(0)));
+
+        let writer = {
+            let cow = cow.clone();
+            thread::spawn(move || cow.edit(|v| v.set(v.get() + 1)))
+        };
+        let reader = {
+            let cow = cow.clone();
+            thread::spawn(move || {
+                let value = cow.read().get();
+                assert!(value <= 2, "Read torn value {}", value);
+            })
+        };
+
+        cow.edit(|v| v.set(v.get() + 1));
+        writer.join().unwrap();
+        reader.join().unwrap();
+        assert_eq!(cow.read().get(), 2);
+    });
+}
+
+#[test]
+fn loom_reads_are_monotonic() {
+    loom::model(|| {
+        let cow = Arc::new(SyncCow::new(Tracked::new(0)));
+
+        // Two edits make the writer publish into the reader's initial buffer again while the
+        // reader may still use the outdated `latest` index
+        let writer = {
+            let cow = cow.clone();
+            thread::spawn(move || {
+                cow.edit(|v| v.set(1));
+                cow.edit(|v| v.set(2));
+            })
+        };
+
+        let first = cow.read().get();
+        let second = cow.read().get();
+        assert!(first <= second, "Read {} after {}", second, first);
+        writer.join().unwrap();
+    });
+}
+
+#[test]
+fn loom_read_previous_never_returns_pending_value() {
+    loom::model(|| {
+        let cow = Arc::new(SyncCow::new(Tracked::new(0)));
+
+        // The second edit publishes into the buffer holding the previous value
+        let writer = {
+            let cow = cow.clone();
+            thread::spawn(move || {
+                cow.edit(|v| v.set(1));
+                cow.edit(|v| v.set(2));
+            })
+        };
+
+        let previous = cow.read_previous().get();
+        assert!(previous <= 1, "Read pending value {} as previous", previous);
+        writer.join().unwrap();
+        assert_eq!(cow.read_previous().get(), 1);
+    });
+}
+
+#[test]
+fn loom_rollback() {
+    loom::model(|| {
+        let cow = Arc::new(SyncCow::new(Tracked::new(1)));
+
+        let writer = {
+            let cow = cow.clone();
+            thread::spawn(move || {
+                cow.edit(|v| v.set(2));
+                cow.rollback();
+            })
+        };
+
+        let value = cow.read().get();
+        assert!(value == 1 || value == 2, "Read torn value {}", value);
+        assert_eq!(cow.read_previous().get(), 1);
+        writer.join().unwrap();
+        assert_eq!(cow.read().get(), 1);
+    });
+}
@@ -0,0 +1,100 @@
+//! Editing map-like values entry by entry, see [`SyncCowMapExt`]
+
+use crate::SyncCow;
+use alloc::collections::BTreeMap;
+
+/// Edits of single entries of a SyncCow holding a map.
+///
+/// Each method is a single [`edit`](SyncCow::edit): the map is cloned once, the entry is
+/// changed and the new map is published, so readers either see the map before or after the
+/// change. Implemented for `BTreeMap` and, with the `std` feature, `HashMap`.
+///
+/// ```
+/// use std::collections::HashMap;
+/// use sync_cow::{SyncCow, SyncCowMapExt};
+///
+/// let hits: SyncCow<HashMap<&str, u32>> = SyncCow::new(HashMap::new());
+/// hits.edit_entry("index", |n| *n += 1);
+/// hits.edit_entry("index", |n| *n += 1);
+/// assert_eq!(hits.read()["index"], 2);
+/// assert_eq!(hits.remove(&"index"), Some(2));
+/// ```
+pub trait SyncCowMapExt<K, V> {
+    /// Edit the value stored for `key`, inserting `V::default()` first if there is none.
+    /// Blocks to acquire write-lock.
+    fn edit_entry<F, R>(&self, key: K, f: F) -> R
+    where
+        V: Default,
+        F: FnOnce(&mut V) -> R;
+
+    /// Insert `value` for `key`, returning the value previously stored for it.
+    /// Blocks to acquire write-lock.
+    fn insert(&self, key: K, value: V) -> Option<V>;
+
+    /// Remove the value stored for `key` and return it. Blocks to acquire write-lock.
+    ///
+    /// Publishes nothing if there is no such value.
+    fn remove(&self, key: &K) -> Option<V>;
+}
+
+impl<K: Ord + Clone, V: Clone> SyncCowMapExt<K, V> for SyncCow<BTreeMap<K, V>> {
+    fn edit_entry<F, R>(&self, key: K, f: F) -> R
+    where
+        V: Default,
+        F: FnOnce(&mut V) -> R,
+    {
+        self.edit(|map| f(map.entry(key).or_default()))
+    }
+
+    fn insert(&self, key: K, value: V) -> Option<V> {
+        self.edit(|map| map.insert(key, value))
+    }
+
+    fn remove(&self, key: &K) -> Option<V> {
+        // Checking first avoids cloning the map for a missing key, `edit_if` covers a concurrent
+        // removal in between
+        if !self.peek(|map| map.contains_key(key)) {
+            return None;
+        }
+        let mut removed = None;
+        let _ = self.edit_if(|map| {
+            removed = map.remove(key);
+            removed.is_some()
+        });
+        removed
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K, V, S> SyncCowMapExt<K, V> for SyncCow<std::collections::HashMap<K, V, S>>
+where
+    K: Eq + core::hash::Hash + Clone,
+    V: Clone,
+    S: core::hash::BuildHasher + Clone,
+{
+    fn edit_entry<F, R>(&self, key: K, f: F) -> R
+    where
+        V: Default,
+        F: FnOnce(&mut V) -> R,
+    {
+        self.edit(|map| f(map.entry(key).or_default()))
+    }
+
+    fn insert(&self, key: K, value: V) -> Option<V> {
+        self.edit(|map| map.insert(key, value))
+    }
+
+    fn remove(&self, key: &K) -> Option<V> {
+        // Checking first avoids cloning the map for a missing key, `edit_if` covers a concurrent
+        // removal in between
+        if !self.peek(|map| map.contains_key(key)) {
+            return None;
+        }
+        let mut removed = None;
+        let _ = self.edit_if(|map| {
+            removed = map.remove(key);
+            removed.is_some()
+        });
+        removed
+    }
+}
@@ -0,0 +1,59 @@
+//! Handle to a part of a read value, see [`SyncCow::read_map`](crate::SyncCow::read_map)
+
+use alloc::sync::Arc;
+use core::fmt;
+use core::ops::Deref;
+
+/// A reference to a part of a value read from a SyncCow, keeping the whole value alive.
+///
+/// Created by [`SyncCow::read_map`](crate::SyncCow::read_map). Like an `Arc<T>`, it keeps
+/// the value it was read from alive, but derefs to the projected `U`.
+pub struct MappedArc<T, U: ?Sized> {
+    arc: Arc<T>,
+    part: *const U,
+}
+
+impl<T, U: ?Sized> MappedArc<T, U> {
+    pub(crate) fn new<F>(arc: Arc<T>, f: F) -> MappedArc<T, U>
+    where
+        F: for<'a> FnOnce(&'a T) -> &'a U,
+    {
+        let part: *const U = f(&arc);
+        MappedArc { arc, part }
+    }
+
+    /// Get the Arc holding the whole value the part was projected from
+    pub fn parent(this: &Self) -> &Arc<T> {
+        &this.arc
+    }
+}
+
+impl<T, U: ?Sized> Deref for MappedArc<T, U> {
+    type Target = U;
+
+    fn deref(&self) -> &U {
+        // The part borrows from the Arc's value, which neither moves nor is dropped while the
+        // Arc is held, or is `'static` altogether.
+        unsafe { &*self.part }
+    }
+}
+
+impl<T, U: ?Sized> Clone for MappedArc<T, U> {
+    fn clone(&self) -> Self {
+        MappedArc {
+            arc: Arc::clone(&self.arc),
+            part: self.part,
+        }
+    }
+}
+
+impl<T, U: ?Sized + fmt::Debug> fmt::Debug for MappedArc<T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+// The raw pointer opts out of the auto traits. Sharing or sending the handle shares the `Arc<T>`
+// and hands out `&U`, which requires what `Arc<T>` and `&U` require.
+unsafe impl<T: Send + Sync, U: ?Sized + Sync> Send for MappedArc<T, U> {}
+unsafe impl<T: Send + Sync, U: ?Sized + Sync> Sync for MappedArc<T, U> {}
@@ -0,0 +1,42 @@
+//! Conveniences for SyncCows holding an optional value, see [`SyncCowOption`]
+
+use crate::SyncCow;
+
+/// A SyncCow holding an optional value, for data that may be absent, e.g. before it's loaded.
+///
+/// Readers use [`read_or`](SyncCow::read_or) to fall back to a default while it's absent, and
+/// writers [`set_some`](SyncCow::set_some) and [`clear`](SyncCow::clear) it. All other methods of
+/// SyncCow work as usual, e.g. [`take`](SyncCow::take) returns the value and clears it.
+///
+/// ```
+/// let token: sync_cow::SyncCowOption<String> = sync_cow::SyncCow::new(None);
+/// assert_eq!(token.read_or(String::from("anonymous")), "anonymous");
+/// token.set_some(String::from("moo"));
+/// assert_eq!(token.read_or(String::from("anonymous")), "moo");
+/// assert_eq!(*token.take(), Some(String::from("moo")));
+/// assert!(token.read().is_none());
+/// ```
+pub type SyncCowOption<T> = SyncCow<Option<T>>;
+
+impl<T> SyncCow<Option<T>> {
+    /// Get a clone of the current value, or `default` if there is none.
+    ///
+    /// Lock-less like [`get`](SyncCow::get).
+    #[must_use]
+    pub fn read_or(&self, default: T) -> T
+    where
+        T: Clone,
+    {
+        self.peek(|value| value.clone().unwrap_or(default))
+    }
+
+    /// Replace the contents with `Some(value)`. Blocks to acquire write-lock.
+    pub fn set_some(&self, value: T) {
+        self.set(Some(value));
+    }
+
+    /// Replace the contents with `None`. Blocks to acquire write-lock.
+    pub fn clear(&self) {
+        self.set(None);
+    }
+}
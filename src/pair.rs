@@ -0,0 +1,48 @@
+//! Editing two SyncCows together, see [`edit_pair`]
+
+use crate::{unique_mut, SyncCow};
+
+/// Edit the contents of two SyncCows together. Blocks to acquire both write-locks.
+///
+/// Acquires the write-locks of `a` and `b`, clones both values and calls `edit_fn` with the
+/// clones, then publishes both. The write-locks are always acquired in the order of the
+/// SyncCows' addresses, so concurrent `edit_pair` calls on the same SyncCows in swapped order
+/// don't deadlock. Writers of either SyncCow wait until both values are published, while
+/// readers never block, like with [`edit`](SyncCow::edit). If `edit_fn` panics, neither value
+/// is published.
+///
+/// The two publishes are not atomic for readers: `a` is published first, so a reader may read
+/// the new value of `a` and still the old value of `b` for a moment. Use a single SyncCow
+/// holding both values if readers need to see them consistently.
+///
+/// `a` and `b` must be different SyncCows, editing one SyncCow as both deadlocks, or panics
+/// with the `std` feature.
+///
+/// ```
+/// let names = sync_cow::SyncCow::new(vec!["moo"]);
+/// let count = sync_cow::SyncCow::new(1);
+/// sync_cow::edit_pair(&names, &count, |names, count| {
+///     names.push("mooo");
+///     *count += 1;
+/// });
+/// assert_eq!(*names.read(), ["moo", "mooo"]);
+/// assert_eq!(*count.read(), 2);
+/// ```
+pub fn edit_pair<A, B, F, R>(a: &SyncCow<A>, b: &SyncCow<B>, edit_fn: F) -> R
+where
+    F: FnOnce(&mut A, &mut B) -> R,
+{
+    let (a_addr, b_addr) = (a as *const _ as usize, b as *const _ as usize);
+    let (_a_lock, _b_lock) = if a_addr <= b_addr {
+        (a.write_lock.lock(), b.write_lock.lock())
+    } else {
+        let b_lock = b.write_lock.lock();
+        (a.write_lock.lock(), b_lock)
+    };
+
+    let (mut a_clone, mut b_clone) = (a.clone_latest_locked(), b.clone_latest_locked());
+    let result = edit_fn(unique_mut(&mut a_clone), unique_mut(&mut b_clone));
+    a.publish_locked(a_clone);
+    b.publish_locked(b_clone);
+    result
+}
@@ -0,0 +1,44 @@
+//! Pinned snapshot of a SyncCow's value, see [`SyncCow::read_guard`](crate::SyncCow::read_guard)
+
+use crate::SyncCow;
+use alloc::sync::Arc;
+use core::fmt;
+use core::ops::Deref;
+
+/// A snapshot of a SyncCow's value that can be refreshed in place.
+///
+/// Created by [`SyncCow::read_guard`](crate::SyncCow::read_guard). Like the Arc returned by
+/// [`read`](crate::SyncCow::read), it keeps the value it was read from alive, and derefs to it
+/// until [`refresh`](ReadGuard::refresh) is called.
+pub struct ReadGuard<'a, T> {
+    cow: &'a SyncCow<T>,
+    arc: Arc<T>,
+}
+
+impl<'a, T> ReadGuard<'a, T> {
+    pub(crate) fn new(cow: &'a SyncCow<T>) -> ReadGuard<'a, T> {
+        ReadGuard {
+            cow,
+            arc: cow.read(),
+        }
+    }
+
+    /// Replace the snapshot with the SyncCow's current value
+    pub fn refresh(&mut self) {
+        self.arc = self.cow.read();
+    }
+}
+
+impl<T> Deref for ReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.arc
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ReadGuard<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
@@ -0,0 +1,34 @@
+//! Borrowed read of a SyncCow's value, see [`SyncCow::borrow`](crate::SyncCow::borrow)
+
+use crate::buffer::Reader;
+use core::fmt;
+use core::ops::Deref;
+
+/// A borrow of a SyncCow's current value, without cloning its Arc.
+///
+/// Created by [`SyncCow::borrow`](crate::SyncCow::borrow). Like a running
+/// [`peek`](crate::SyncCow::peek) closure, the guard marks the buffer holding the value as being
+/// read until it's dropped, so a writer publishing into that buffer waits for it.
+pub struct ReadRef<'a, T> {
+    reader: Reader<'a, T>,
+}
+
+impl<'a, T> ReadRef<'a, T> {
+    pub(crate) fn new(reader: Reader<'a, T>) -> ReadRef<'a, T> {
+        ReadRef { reader }
+    }
+}
+
+impl<T> Deref for ReadRef<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.reader
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ReadRef<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
@@ -0,0 +1,18 @@
+//! `serde` support, serializing and deserializing a SyncCow transparently as its value
+
+use crate::SyncCow;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Serializes a lock-less [`read`](SyncCow::read) snapshot of the value, transparently as `T`.
+impl<T: Serialize> Serialize for SyncCow<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.read().as_ref().serialize(serializer)
+    }
+}
+
+/// Deserializes a `T` and wraps it in a new SyncCow.
+impl<'de, T: Clone + Deserialize<'de>> Deserialize<'de> for SyncCow<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(SyncCow::new)
+    }
+}
@@ -0,0 +1,68 @@
+//! Owned or shared value, see [`SyncCow::snapshot`](crate::SyncCow::snapshot)
+
+use alloc::sync::Arc;
+use core::fmt;
+use core::ops::Deref;
+
+/// A value read from a SyncCow, either shared with the SyncCow or owned.
+///
+/// Created by [`SyncCow::snapshot`](crate::SyncCow::snapshot), which returns a `Shared`
+/// snapshot, or from an owned value with `From`. Code generic over ownership can deref to the
+/// value either way, and call [`into_owned`](Snapshot::into_owned) once it needs to own it.
+pub enum Snapshot<T> {
+    /// A value shared with the SyncCow and other readers
+    Shared(Arc<T>),
+    /// A value owned by the snapshot
+    Owned(T),
+}
+
+impl<T> Snapshot<T> {
+    /// Take ownership of the value.
+    ///
+    /// A shared value is unwrapped if no other Arc of it is alive, e.g. because the SyncCow
+    /// released it after later edits, and cloned otherwise.
+    pub fn into_owned(self) -> T
+    where
+        T: Clone,
+    {
+        match self {
+            Snapshot::Shared(arc) => Arc::unwrap_or_clone(arc),
+            Snapshot::Owned(value) => value,
+        }
+    }
+}
+
+impl<T> Deref for Snapshot<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match self {
+            Snapshot::Shared(arc) => arc,
+            Snapshot::Owned(value) => value,
+        }
+    }
+}
+
+impl<T> AsRef<T> for Snapshot<T> {
+    fn as_ref(&self) -> &T {
+        self
+    }
+}
+
+impl<T> From<Arc<T>> for Snapshot<T> {
+    fn from(arc: Arc<T>) -> Self {
+        Snapshot::Shared(arc)
+    }
+}
+
+impl<T> From<T> for Snapshot<T> {
+    fn from(value: T) -> Self {
+        Snapshot::Owned(value)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Snapshot<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
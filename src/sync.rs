@@ -0,0 +1,315 @@
+//! Synchronization primitives abstracting over `std`, `no_std` and loom builds
+//!
+//! With `--cfg loom` the atomics, the write-lock and the backoff are replaced by their loom
+//! counterparts, so loom can explore all interleavings of the SyncCow protocol.
+
+use crate::instrument;
+use crate::Backoff;
+// Thread bookkeeping is not part of the reader/writer protocol, so loom doesn't need to model it
+use core::sync::atomic::{AtomicUsize as PlainAtomicUsize, Ordering::Relaxed};
+
+#[cfg(loom)]
+pub(crate) use loom::sync::atomic::{fence, AtomicPtr, AtomicU32, AtomicU64, AtomicUsize};
+#[cfg(not(loom))]
+pub(crate) use core::sync::atomic::{fence, AtomicPtr, AtomicU32, AtomicUsize};
+
+// Targets without native 64-bit atomics fall back to portable-atomic's lock-based AtomicU64
+#[cfg(all(not(loom), target_has_atomic = "64"))]
+pub(crate) use core::sync::atomic::AtomicU64;
+#[cfg(all(not(loom), not(target_has_atomic = "64")))]
+pub(crate) use portable_atomic::AtomicU64;
+
+#[cfg(loom)]
+type InnerMutex<T> = loom::sync::Mutex<T>;
+#[cfg(loom)]
+pub(crate) type MutexGuard<'a, T> = loom::sync::MutexGuard<'a, T>;
+
+#[cfg(all(feature = "std", not(loom)))]
+type InnerMutex<T> = std::sync::Mutex<T>;
+#[cfg(all(feature = "std", not(loom)))]
+pub(crate) type MutexGuard<'a, T> = std::sync::MutexGuard<'a, T>;
+
+#[cfg(not(any(feature = "std", loom)))]
+type InnerMutex<T> = spin::Mutex<T>;
+#[cfg(not(any(feature = "std", loom)))]
+pub(crate) type MutexGuard<'a, T> = spin::MutexGuard<'a, T>;
+
+// With the `parking_lot` feature, only the write-lock uses parking_lot, the other mutexes are
+// paired with std's `Condvar`
+#[cfg(all(feature = "parking_lot", not(loom)))]
+type WriteMutex = parking_lot::Mutex<()>;
+#[cfg(all(feature = "parking_lot", not(loom)))]
+type WriteMutexGuard<'a> = parking_lot::MutexGuard<'a, ()>;
+
+#[cfg(not(all(feature = "parking_lot", not(loom))))]
+type WriteMutex = Mutex<()>;
+#[cfg(not(all(feature = "parking_lot", not(loom))))]
+type WriteMutexGuard<'a> = MutexGuard<'a, ()>;
+
+/// The write-lock serializing writers of a SyncCow
+///
+/// Panics instead of deadlocking if the thread holding the lock tries to acquire it again, e.g.
+/// by editing the SyncCow from within an edit closure. Reentrance is only detected in std builds.
+pub(crate) struct WriteLock {
+    lock: WriteMutex,
+    /// Index + 1 of the thread holding the lock, 0 if unknown or not held
+    owner: PlainAtomicUsize,
+    /// Wakes async writers waiting for the lock, see [`WriteLock::lock_async`]
+    #[cfg(feature = "async")]
+    released: tokio::sync::Notify,
+}
+
+/// Guard of a [`WriteLock`], releasing it on drop
+pub(crate) struct WriteGuard<'a> {
+    /// Only `None` while the guard is dropped
+    guard: Option<WriteMutexGuard<'a>>,
+    lock: &'a WriteLock,
+}
+
+impl WriteLock {
+    pub(crate) fn new() -> WriteLock {
+        WriteLock {
+            lock: WriteMutex::new(()),
+            owner: PlainAtomicUsize::new(0),
+            #[cfg(feature = "async")]
+            released: tokio::sync::Notify::new(),
+        }
+    }
+
+    /// Block until the write-lock is acquired
+    pub(crate) fn lock(&self) -> WriteGuard<'_> {
+        self.check_reentrance();
+        let guard = instrument::write_lock_wait(|| self.lock.lock());
+        self.guard(guard)
+    }
+
+    /// Acquire the write-lock if it's not held by another writer
+    pub(crate) fn try_lock(&self) -> Option<WriteGuard<'_>> {
+        self.check_reentrance();
+        self.lock.try_lock().map(|guard| self.guard(guard))
+    }
+
+    /// Wait for the write-lock without blocking the thread, woken whenever the lock is released.
+    ///
+    /// Reentrance isn't checked: tasks share threads, so an async writer running on the thread
+    /// of a task holding the lock, e.g. through an `EditGuard` held across an await, isn't
+    /// reentrant. Such a writer simply waits until the other task releases the lock.
+    #[cfg(feature = "async")]
+    pub(crate) async fn lock_async(&self) -> WriteGuard<'_> {
+        loop {
+            let released = self.released.notified();
+            tokio::pin!(released);
+            // Registers the waiter before trying, so a release in between isn't missed
+            released.as_mut().enable();
+            if let Some(guard) = self.lock.try_lock() {
+                return self.guard(guard);
+            }
+            released.await;
+        }
+    }
+
+    /// Whether the calling thread holds the write-lock. Always `false` in no_std builds.
+    pub(crate) fn is_reentrant(&self) -> bool {
+        // Only the owning thread stores its own index, so it always observes it here, while
+        // other threads never do. Relaxed suffices for that.
+        thread_index().is_some_and(|index| self.owner.load(Relaxed) == index + 1)
+    }
+
+    fn check_reentrance(&self) {
+        if self.is_reentrant() {
+            panic!("reentrant edit detected: the SyncCow is already being edited by this thread");
+        }
+    }
+
+    fn guard<'a>(&'a self, guard: WriteMutexGuard<'a>) -> WriteGuard<'a> {
+        let owner = thread_index().map_or(0, |index| index + 1);
+        self.owner.store(owner, Relaxed);
+        WriteGuard {
+            guard: Some(guard),
+            lock: self,
+        }
+    }
+}
+
+impl Drop for WriteGuard<'_> {
+    fn drop(&mut self) {
+        // Cleared while the lock is still held
+        self.lock.owner.store(0, Relaxed);
+        drop(self.guard.take());
+        // Only wake async writers once the lock can be acquired
+        #[cfg(feature = "async")]
+        self.lock.released.notify_waiters();
+    }
+}
+
+/// Mutex of the std, loom or spin flavor, depending on the build
+///
+/// Poisoning is ignored: the SyncCow's state is consistent wherever a panic can occur while a
+/// mutex is held. A panicking edit closure runs before anything is published, and observers run
+/// after the publish has completed.
+pub(crate) struct Mutex<T>(InnerMutex<T>);
+
+impl<T> Mutex<T> {
+    pub(crate) fn new(value: T) -> Mutex<T> {
+        Mutex(InnerMutex::new(value))
+    }
+
+    /// Block until the mutex is acquired
+    #[cfg(any(feature = "std", loom))]
+    pub(crate) fn lock(&self) -> MutexGuard<'_, T> {
+        self.0.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Acquire the mutex if it's not held by someone else
+    #[cfg(any(feature = "std", loom))]
+    #[cfg_attr(all(feature = "parking_lot", not(loom)), allow(dead_code))]
+    pub(crate) fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        match self.0.try_lock() {
+            Ok(lck) => Some(lck),
+            Err(std::sync::TryLockError::WouldBlock) => None,
+            Err(std::sync::TryLockError::Poisoned(err)) => Some(err.into_inner()),
+        }
+    }
+
+    /// Spin until the mutex is acquired
+    #[cfg(not(any(feature = "std", loom)))]
+    pub(crate) fn lock(&self) -> MutexGuard<'_, T> {
+        self.0.lock()
+    }
+
+    /// Acquire the mutex if it's not held by someone else
+    #[cfg(not(any(feature = "std", loom)))]
+    pub(crate) fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        self.0.try_lock()
+    }
+}
+
+#[cfg(loom)]
+type Condvar = loom::sync::Condvar;
+#[cfg(all(feature = "std", not(loom)))]
+type Condvar = std::sync::Condvar;
+
+/// Wakes threads waiting for a SyncCow's writers to publish
+#[cfg(feature = "std")]
+pub(crate) struct Notifier {
+    lock: Mutex<()>,
+    published: Condvar,
+}
+
+#[cfg(feature = "std")]
+impl Notifier {
+    pub(crate) fn new() -> Notifier {
+        Notifier {
+            lock: Mutex::new(()),
+            published: Condvar::new(),
+        }
+    }
+
+    /// Wake all waiting threads, called after each publish
+    pub(crate) fn notify(&self) {
+        // Taking the lock orders the notification after a waiter's check of `done`, so it
+        // can't get lost between the check and the waiter parking
+        drop(self.lock.lock());
+        self.published.notify_all();
+    }
+
+    /// Block until `done` returns true, rechecking it after each publish
+    pub(crate) fn wait_until(&self, mut done: impl FnMut() -> bool) {
+        let mut guard = self.lock.lock();
+        while !done() {
+            guard = self
+                .published
+                .wait(guard)
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+        }
+    }
+}
+
+/// Number of reader count stripes per buffer
+///
+/// One per available core, up to 16. Threads are assigned a stripe by their index modulo the
+/// number of stripes, not by the core they run on, so with at most as many reading threads as
+/// cores they rarely share one. Loom and `no_std` builds use a single stripe, and so do builds
+/// with `--cfg sync_cow_unstriped`, the baseline of the `read_throughput` bench.
+#[cfg(all(feature = "std", not(loom), not(sync_cow_unstriped)))]
+pub(crate) fn reader_stripes() -> usize {
+    use std::sync::OnceLock;
+    static STRIPES: OnceLock<usize> = OnceLock::new();
+    *STRIPES.get_or_init(|| {
+        std::thread::available_parallelism()
+            .map(|cores| cores.get().min(16))
+            .unwrap_or(1)
+    })
+}
+
+#[cfg(not(all(feature = "std", not(loom), not(sync_cow_unstriped))))]
+pub(crate) fn reader_stripes() -> usize {
+    1
+}
+
+/// Sequential index of the calling thread, `None` while thread-locals are destroyed or in loom
+/// and `no_std` builds
+#[cfg(all(feature = "std", not(loom)))]
+fn thread_index() -> Option<usize> {
+    static NEXT_INDEX: PlainAtomicUsize = PlainAtomicUsize::new(0);
+    std::thread_local! {
+        static INDEX: usize = NEXT_INDEX.fetch_add(1, Relaxed);
+    }
+    INDEX.try_with(|index| *index).ok()
+}
+
+#[cfg(not(all(feature = "std", not(loom))))]
+fn thread_index() -> Option<usize> {
+    None
+}
+
+/// Reader count stripe of the calling thread, to be taken modulo the number of stripes
+pub(crate) fn reader_stripe() -> usize {
+    // Falls back to the first stripe while thread-locals are destroyed
+    thread_index().unwrap_or(0)
+}
+
+/// Checks of [`Backoff::Exponential`] spinning `2^step` times before yielding
+#[cfg(not(loom))]
+const SPIN_STEPS: u64 = 6;
+/// Checks of [`Backoff::Exponential`] yielding before sleeping
+#[cfg(all(feature = "std", not(loom)))]
+const YIELD_STEPS: u64 = 16;
+/// Cap of the sleep duration of [`Backoff::Exponential`], in microseconds
+#[cfg(all(feature = "std", not(loom)))]
+const MAX_SLEEP_MICROS: u64 = 1000;
+
+/// Back off while waiting for readers to finish, `step` counting the previous backoffs of the
+/// current wait
+pub(crate) fn backoff(backoff: Backoff, step: u64) {
+    #[cfg(loom)]
+    {
+        // Loom needs to know the thread is waiting, otherwise it explores spinning forever
+        let _ = (backoff, step);
+        loom::thread::yield_now();
+    }
+    #[cfg(all(feature = "std", not(loom)))]
+    match backoff {
+        Backoff::Yield => std::thread::yield_now(),
+        Backoff::Spin => core::hint::spin_loop(),
+        Backoff::Exponential if step < SPIN_STEPS => spin(1 << step),
+        Backoff::Exponential if step < YIELD_STEPS => std::thread::yield_now(),
+        Backoff::Exponential => {
+            let micros = 1u64 << (step - YIELD_STEPS).min(10);
+            let sleep = std::time::Duration::from_micros(micros.min(MAX_SLEEP_MICROS));
+            std::thread::sleep(sleep);
+        }
+    }
+    #[cfg(not(any(feature = "std", loom)))]
+    match backoff {
+        Backoff::Exponential => spin(1 << step.min(SPIN_STEPS)),
+        Backoff::Yield | Backoff::Spin => core::hint::spin_loop(),
+    }
+}
+
+#[cfg(not(loom))]
+fn spin(times: u64) {
+    for _ in 0..times {
+        core::hint::spin_loop();
+    }
+}
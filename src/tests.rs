@@ -1,8 +1,13 @@
 use crate::*;
 use std::sync::atomic::AtomicBool;
-use std::sync::RwLock;
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::{Mutex, RwLock};
+
+// All tests except the timing-based ones are small enough to run under Miri
+// (`cargo +nightly miri test`), which checks the unsafe pointer handoff for undefined behavior.
 
 #[test]
+#[cfg_attr(miri, ignore)]
 fn cow_faster_than_rwlock_nosleep() {
     let reader_sleep = Some(std::time::Duration::from_millis(5));
     let writer_sleep = Some(std::time::Duration::from_millis(10));
@@ -133,3 +138,1957 @@ fn write_and_read_alot(
     let read_count = *global_counter.lock().unwrap();
     (time, read_count)
 }
+
+#[test]
+fn try_edit_returns_would_block_when_contended() {
+    let cow = Arc::new(SyncCow::new(5));
+    let (entered_tx, entered_rx) = std::sync::mpsc::channel();
+    let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+
+    let cow_clone = cow.clone();
+    let writer = std::thread::spawn(move || {
+        cow_clone.edit(|x| {
+            entered_tx.send(()).unwrap();
+            release_rx.recv().unwrap();
+            *x = 6;
+        });
+    });
+
+    entered_rx.recv().unwrap();
+    let mut called = false;
+    assert_eq!(cow.try_edit(|_| called = true), Err(SyncCowError::WouldBlock));
+    assert!(!called, "try_edit must not run the closure when the lock is contended");
+
+    release_tx.send(()).unwrap();
+    writer.join().unwrap();
+    assert_eq!(*cow.read(), 6);
+    assert_eq!(cow.try_edit(|x| *x = 7), Ok(()));
+    assert_eq!(*cow.read(), 7);
+}
+
+#[test]
+fn edit_returns_closure_result() {
+    let cow = SyncCow::new(vec![1, 2]);
+    let len = cow.edit(|v| {
+        v.push(3);
+        v.len()
+    });
+    assert_eq!(len, 3);
+    assert_eq!(*cow.read(), vec![1, 2, 3]);
+}
+
+/// Panics on `clone()` once armed, to assert code paths that must not clone.
+struct PanicOnClone {
+    value: i32,
+    armed: Arc<AtomicBool>,
+}
+
+impl Clone for PanicOnClone {
+    fn clone(&self) -> Self {
+        assert!(!self.armed.load(Relaxed), "PanicOnClone was cloned");
+        PanicOnClone {
+            value: self.value,
+            armed: self.armed.clone(),
+        }
+    }
+}
+
+#[test]
+fn set_does_not_clone() {
+    let armed = Arc::new(AtomicBool::new(false));
+    let cow = SyncCow::new(PanicOnClone {
+        value: 1,
+        armed: armed.clone(),
+    });
+    armed.store(true, Relaxed);
+
+    let val = cow.read();
+    for value in 2..5 {
+        cow.set(PanicOnClone {
+            value,
+            armed: armed.clone(),
+        });
+        assert_eq!(cow.read().value, value);
+    }
+    assert_eq!(val.value, 1);
+}
+
+#[test]
+fn replace_returns_previous_value() {
+    let cow = SyncCow::new(String::from("moo"));
+    let held = cow.read();
+    let old = cow.replace(String::from("mooo"));
+    assert!(Arc::ptr_eq(&held, &old), "replace should return the Arc readers were seeing");
+    assert_eq!(*old, "moo");
+    assert_eq!(*cow.read(), "mooo");
+
+    let old = cow.replace(String::from("moooo"));
+    assert_eq!(*old, "mooo");
+    assert_eq!(*cow.read(), "moooo");
+}
+
+#[test]
+fn default_holds_default_value() {
+    let c: SyncCow<Vec<u8>> = Default::default();
+    assert!(c.read().is_empty());
+}
+
+#[test]
+fn debug_contains_current_value() {
+    let cow = SyncCow::new(vec![1, 2]);
+    assert_eq!(format!("{:?}", cow), "SyncCow { value: [1, 2], latest: 0 }");
+    cow.edit(|v| v.push(3));
+    assert_eq!(format!("{:?}", cow), "SyncCow { value: [1, 2, 3], latest: 1 }");
+}
+
+#[test]
+fn clone_is_independent() {
+    let cow = SyncCow::new(5);
+    let cloned = cow.clone();
+    cow.edit(|x| *x = 6);
+    assert_eq!(*cow.read(), 6);
+    assert_eq!(*cloned.read(), 5);
+    cloned.edit(|x| *x = 7);
+    assert_eq!(*cow.read(), 6);
+    assert_eq!(*cloned.read(), 7);
+}
+
+#[test]
+fn clone_keeps_configuration() {
+    let clones = Arc::new(AtomicUsize::new(0));
+    let cow = SyncCow::builder()
+        .buffers(4)
+        .backoff(Backoff::Spin)
+        .build(CloneCounter {
+            clones: clones.clone(),
+        });
+    let before = clones.load(Relaxed);
+    let cloned = cow.clone();
+    assert_eq!(clones.load(Relaxed), before + 1, "the value is cloned once");
+    assert_eq!(cloned.debug_buffers().0.len(), 4);
+    assert_eq!(cloned.backoff, Backoff::Spin);
+
+    let custom = SyncCow::with_cloner(vec![1], |v| vec![v[0] + 10]);
+    let cloned = custom.clone();
+    cloned.edit(|v| v.push(2));
+    assert_eq!(*cloned.read(), [11, 2], "edits of the clone use the custom cloner");
+}
+
+#[test]
+fn from_matches_new() {
+    let new = SyncCow::new(5);
+    let from: SyncCow<i32> = 5.into();
+    assert_eq!(*new.read(), *from.read());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trip() {
+    #[derive(serde::Serialize, serde::Deserialize)]
+    struct Config {
+        names: SyncCow<Vec<String>>,
+    }
+
+    let config = Config {
+        names: SyncCow::new(vec![String::from("moo")]),
+    };
+    config.names.edit(|n| n.push(String::from("mooo")));
+
+    let json = serde_json::to_string(&config).unwrap();
+    assert_eq!(json, r#"{"names":["moo","mooo"]}"#);
+
+    let config: Config = serde_json::from_str(&json).unwrap();
+    assert_eq!(*config.names.read(), vec!["moo", "mooo"]);
+}
+
+#[test]
+fn single_writer_single_reader() {
+    let cow = Arc::new(SyncCow::new(0));
+    let reader_cow = cow.clone();
+    let reader = std::thread::spawn(move || {
+        let mut reads = vec![];
+        while reads.last() != Some(&10) {
+            reads.push(*reader_cow.read());
+        }
+        reads
+    });
+    for _ in 0..10 {
+        cow.edit(|x| *x += 1);
+    }
+    let reads = reader.join().unwrap();
+    assert!(reads.iter().all(|x| (0..=10).contains(x)));
+    assert_eq!(*cow.read(), 10);
+}
+
+#[test]
+fn version_counts_publishes() {
+    let cow = SyncCow::new(0);
+    let start = cow.version();
+    for _ in 0..5 {
+        cow.edit(|x| *x += 1);
+    }
+    cow.set(10);
+    let _ = cow.replace(11);
+    assert_eq!(cow.version(), start + 7);
+}
+
+#[test]
+fn read_if_changed_detects_edits() {
+    let cow = SyncCow::new(5);
+    let version = cow.version();
+    assert!(cow.read_if_changed(version).is_none());
+
+    cow.edit(|x| *x = 6);
+    let (val, new_version) = cow.read_if_changed(version).unwrap();
+    assert_eq!(*val, 6);
+    assert_eq!(new_version, version + 1);
+    assert!(cow.read_if_changed(new_version).is_none());
+}
+
+#[test]
+fn read_if_modified_since_detects_edits() {
+    let cow = SyncCow::new(5);
+    let created = cow.last_modified();
+    let before_edit = std::time::Instant::now();
+    assert!(cow.read_if_modified_since(before_edit).is_none());
+
+    cow.edit(|x| *x = 6);
+    let modified = cow.last_modified();
+    assert!(modified > created);
+    assert!(modified >= before_edit);
+    assert_eq!(*cow.read_if_modified_since(before_edit).unwrap(), 6);
+    assert!(cow.read_if_modified_since(modified).is_none());
+}
+
+#[test]
+fn get_mut_is_not_tracked_as_modification() {
+    let mut cow = SyncCow::new(5);
+    let modified = cow.last_modified();
+    *cow.get_mut() = 6;
+    assert_eq!(cow.last_modified(), modified);
+    assert!(cow.read_if_modified_since(modified).is_none());
+}
+
+#[test]
+fn edit_if_publishes_on_true() {
+    let cow = SyncCow::new(vec![1]);
+    assert!(cow.edit_if(|v| {
+        v.push(2);
+        true
+    }));
+    assert_eq!(*cow.read(), vec![1, 2]);
+    assert_eq!(cow.version(), 1);
+}
+
+#[test]
+fn edit_if_discards_on_false() {
+    let cow = SyncCow::new(vec![1]);
+    let before = cow.read();
+    assert!(!cow.edit_if(|v| {
+        v.push(2);
+        false
+    }));
+    assert!(Arc::ptr_eq(&before, &cow.read()), "Aborted edit must not publish");
+    assert_eq!(cow.version(), 0);
+    // The write-lock has been released
+    cow.edit(|v| v.push(3));
+    assert_eq!(*cow.read(), vec![1, 3]);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn edit_async_serializes_writers() {
+    let cow = Arc::new(SyncCow::new(vec![]));
+    let edit = |cow: Arc<SyncCow<Vec<&'static str>>>| async move {
+        cow.edit_async(|v| {
+            v.push("enter");
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            v.push("exit");
+        })
+        .await
+    };
+
+    let first = tokio::spawn(edit(cow.clone()));
+    let second = tokio::spawn(edit(cow.clone()));
+    // A blocking writer in between must not break serialization either
+    cow.edit(|v| {
+        v.push("enter");
+        v.push("exit");
+    });
+    first.await.unwrap();
+    second.await.unwrap();
+
+    assert_eq!(*cow.read(), ["enter", "exit"].repeat(3));
+}
+
+#[cfg(feature = "async")]
+#[tokio::test(flavor = "current_thread")]
+async fn edit_async_waits_for_blocking_writer_without_polling() {
+    let cow = Arc::new(SyncCow::new(0));
+    let (locked_tx, locked_rx) = std::sync::mpsc::channel();
+    let writer = {
+        let cow = cow.clone();
+        std::thread::spawn(move || {
+            cow.edit(|x| {
+                locked_tx.send(()).unwrap();
+                std::thread::sleep(std::time::Duration::from_millis(50));
+                *x = 1;
+            })
+        })
+    };
+    locked_rx.recv().unwrap();
+
+    let mut edit = std::pin::pin!(cow.edit_async(|x| *x += 1));
+    let mut polls = 0;
+    std::future::poll_fn(|cx| {
+        polls += 1;
+        std::future::Future::poll(edit.as_mut(), cx)
+    })
+    .await;
+    writer.join().unwrap();
+    assert_eq!(*cow.read(), 2);
+    assert!(polls <= 3, "polled {} times while waiting", polls);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test(flavor = "current_thread")]
+async fn edit_async_waits_for_guard_of_task_on_same_thread() {
+    let cow = Arc::new(SyncCow::new(0));
+    let mut guard = cow.edit_guard();
+    *guard = 1;
+    // Runs on this thread while the guard is held, which is not a reentrant edit
+    let waiter = tokio::spawn({
+        let cow = cow.clone();
+        async move { cow.edit_async(|x| *x += 1).await }
+    });
+    tokio::task::yield_now().await;
+    assert!(!waiter.is_finished());
+    drop(guard);
+    waiter.await.unwrap();
+    assert_eq!(*cow.read(), 2);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn watch_observes_latest_value() {
+    let cow = Arc::new(SyncCow::new(0));
+    let mut values = cow.watch();
+    assert_eq!(**values.borrow(), 0);
+
+    let writer = {
+        let cow = cow.clone();
+        std::thread::spawn(move || {
+            for i in 1..=100 {
+                cow.edit(|x| *x = i);
+            }
+        })
+    };
+    while **values.borrow_and_update() != 100 {
+        values.changed().await.unwrap();
+    }
+    writer.join().unwrap();
+
+    let mut late = cow.watch();
+    assert!(!late.has_changed().unwrap());
+    cow.edit(|x| *x += 1);
+    late.changed().await.unwrap();
+    assert_eq!(**late.borrow(), 101);
+
+    // Mutations through `get_mut` are not publishes and are not sent
+    let mut cow = Arc::into_inner(cow).unwrap();
+    let mut owned = cow.watch();
+    *cow.get_mut() = 0;
+    assert!(!owned.has_changed().unwrap());
+    assert_eq!(**owned.borrow_and_update(), 101);
+}
+
+#[test]
+fn try_edit_timeout_times_out_when_contended() {
+    let cow = Arc::new(SyncCow::new(5));
+    let (entered_tx, entered_rx) = std::sync::mpsc::channel();
+    let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+
+    let cow_clone = cow.clone();
+    let writer = std::thread::spawn(move || {
+        cow_clone.edit(|x| {
+            entered_tx.send(()).unwrap();
+            release_rx.recv().unwrap();
+            *x = 6;
+        });
+    });
+
+    entered_rx.recv().unwrap();
+    let timeout = std::time::Duration::from_millis(20);
+    let start = std::time::Instant::now();
+    let mut called = false;
+    assert_eq!(
+        cow.try_edit_timeout(timeout, |_| called = true),
+        Err(SyncCowError::Timeout)
+    );
+    assert!(start.elapsed() >= timeout);
+    assert!(!called, "try_edit_timeout must not run the closure on timeout");
+
+    release_tx.send(()).unwrap();
+    writer.join().unwrap();
+    assert_eq!(cow.try_edit_timeout(timeout, |x| *x = 7), Ok(()));
+    assert_eq!(*cow.read(), 7);
+}
+
+#[test]
+fn get_mut_mutates_in_place() {
+    let mut cow = SyncCow::new(vec![1]);
+    cow.get_mut().push(2);
+    assert_eq!(*cow.read(), vec![1, 2]);
+
+    // Outstanding readers keep their value
+    let held = cow.read();
+    cow.get_mut().push(3);
+    assert_eq!(*held, vec![1, 2]);
+    assert_eq!(*cow.read(), vec![1, 2, 3]);
+    assert_eq!(cow.version(), 0, "get_mut is not a publish");
+
+    cow.edit(|v| v.push(4));
+    assert_eq!(*cow.read(), vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn into_inner_returns_latest_value() {
+    let cow = SyncCow::new(String::from("moo"));
+    cow.edit(|s| s.push('o'));
+    assert_eq!(cow.into_inner(), "mooo");
+
+    // Outstanding readers keep their Arc, the value is cloned
+    let cow = SyncCow::new(String::from("moo"));
+    let held = cow.read();
+    assert_eq!(cow.into_inner(), "moo");
+    assert_eq!(*held, "moo");
+}
+
+#[test]
+fn take_resets_to_default() {
+    let cow = SyncCow::new(vec![1, 2, 3]);
+    let taken = cow.take();
+    assert_eq!(*taken, vec![1, 2, 3]);
+    assert!(cow.read().is_empty());
+    assert!(cow.take().is_empty());
+}
+
+#[test]
+fn partial_eq_compares_values() {
+    let cow = SyncCow::new(5);
+    let other = SyncCow::new(5);
+    assert!(cow == other);
+    assert!(cow == 5);
+
+    other.edit(|x| *x = 6);
+    assert!(cow != other);
+    assert!(other != 5);
+    assert!(other == 6);
+}
+
+/// Counts how often it has been cloned
+#[derive(Debug, Default)]
+struct CloneCounter {
+    clones: Arc<AtomicUsize>,
+}
+
+impl Clone for CloneCounter {
+    fn clone(&self) -> Self {
+        self.clones.fetch_add(1, Relaxed);
+        CloneCounter {
+            clones: self.clones.clone(),
+        }
+    }
+}
+
+#[test]
+fn new_clones_at_most_once() {
+    let counter = CloneCounter::default();
+    let clones = counter.clones.clone();
+    let _cow = SyncCow::new(counter);
+    assert!(clones.load(Relaxed) <= 1);
+}
+
+#[test]
+fn edit_mut_calls_closure_once() {
+    let cow = SyncCow::new(5);
+    let mut counter = 0;
+    let mut edit_fn = |x: &mut i32| {
+        counter += 1;
+        *x += 1;
+    };
+    cow.edit_mut(&mut edit_fn);
+    assert_eq!(counter, 1);
+    assert_eq!(*cow.read(), 6);
+}
+
+#[test]
+fn edit_borrows_stack_locals() {
+    let cow = SyncCow::new(vec![1, 2, 3]);
+    let mut sum = 0;
+    let extra = String::from("4");
+    cow.edit(|v| {
+        v.push(extra.parse().unwrap());
+        sum = v.iter().sum();
+    });
+    assert_eq!(sum, 10);
+
+    let mut popped = vec![];
+    cow.edit_ctx(&mut popped, |v, popped| popped.extend(v.pop()));
+    assert_eq!(popped, [4]);
+    assert_eq!(*cow.read(), [1, 2, 3]);
+}
+
+#[test]
+fn edit_pair_publishes_both() {
+    let names = Arc::new(SyncCow::new(vec![]));
+    let count = Arc::new(SyncCow::new(0));
+    let handles: Vec<_> = (0..4)
+        .map(|i| {
+            let (names, count) = (names.clone(), count.clone());
+            std::thread::spawn(move || {
+                for _ in 0..100 {
+                    // Swapped argument order must not deadlock
+                    if i % 2 == 0 {
+                        edit_pair(&names, &count, |names, count| {
+                            names.push(i);
+                            *count += 1;
+                        });
+                    } else {
+                        edit_pair(&count, &names, |count, names| {
+                            names.push(i);
+                            *count += 1;
+                        });
+                    }
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert_eq!(names.read().len(), 400);
+    assert_eq!(*count.read(), 400);
+}
+
+#[test]
+fn collect_and_extend_collections() {
+    let cow: SyncCow<Vec<i32>> = SyncCow::collect((1..=3).map(|x| x * 10));
+    assert_eq!(*cow.read(), [10, 20, 30]);
+    cow.extend(vec![40, 50]);
+    assert_eq!(*cow.read(), [10, 20, 30, 40, 50]);
+    assert_eq!(cow.version(), 1);
+
+    let names: SyncCow<String> = SyncCow::collect(["m", "o"]);
+    names.extend(['o', 'o']);
+    assert_eq!(*names.read(), "mooo");
+}
+
+#[test]
+fn edit_guard_publishes_on_drop() {
+    let cow = SyncCow::new(vec![1]);
+    {
+        let mut guard = cow.edit_guard();
+        guard.push(2);
+        assert!(cow.try_edit(|v| v.clear()).is_err(), "the guard holds the write-lock");
+    }
+    assert_eq!(*cow.read(), [1, 2]);
+    assert_eq!(cow.version(), 1);
+
+    let mut guard = cow.edit_guard();
+    guard.push(3);
+    assert_eq!(*cow.read(), [1, 2]);
+    drop(guard);
+    assert_eq!(*cow.read(), [1, 2, 3]);
+    cow.edit(|v| v.push(4));
+    assert_eq!(cow.version(), 3);
+}
+
+#[test]
+fn edit_guard_discards_on_panic() {
+    let cow = SyncCow::new(1);
+    let panicking_edit = std::panic::AssertUnwindSafe(|| {
+        let mut guard = cow.edit_guard();
+        *guard = 2;
+        panic!("edit failed");
+    });
+    std::panic::catch_unwind(panicking_edit).unwrap_err();
+    assert_eq!(*cow.read(), 1);
+    assert_eq!(cow.version(), 0);
+    cow.edit(|x| *x = 3);
+    assert_eq!(*cow.read(), 3);
+}
+
+#[test]
+fn on_change_observes_publishes_in_order() {
+    let cow = SyncCow::new(0);
+    let seen = Arc::new(Mutex::new(vec![]));
+    let seen_clone = seen.clone();
+    cow.on_change(move |x| seen_clone.lock().unwrap().push(**x));
+
+    cow.edit(|x| *x = 1);
+    cow.set(2);
+    let _ = cow.replace(3);
+    assert!(!cow.edit_if(|x| {
+        *x = 4;
+        false
+    }));
+    cow.edit(|x| *x += 2);
+    assert_eq!(*seen.lock().unwrap(), vec![1, 2, 3, 5]);
+}
+
+#[test]
+fn with_buffers_rotates_through_buffers() {
+    let cow = SyncCow::with_buffers(0, 4);
+    let held: Vec<_> = (1..=10)
+        .map(|i| {
+            cow.edit(|x| *x = i);
+            cow.read()
+        })
+        .collect();
+    assert_eq!(*cow.read(), 10);
+    assert_eq!(cow.version(), 10);
+    for (i, val) in held.iter().enumerate() {
+        assert_eq!(**val, i + 1);
+    }
+}
+
+#[test]
+#[should_panic(expected = "SyncCow needs at least 2 buffers")]
+fn with_buffers_needs_two_buffers() {
+    let _ = SyncCow::with_buffers(0, 1);
+}
+
+#[test]
+fn edit_batch_publishes_once() {
+    let cow = SyncCow::new((0, CloneCounter::default()));
+    let clones = cow.read().1.clones.clone();
+    let before = clones.load(Relaxed);
+
+    let increment = |x: &mut (i32, CloneCounter)| x.0 += 1;
+    cow.edit_batch([increment; 3]);
+    assert_eq!(cow.read().0, 3);
+    assert_eq!(cow.version(), 1);
+    assert_eq!(clones.load(Relaxed), before + 1);
+}
+
+#[test]
+fn peek_matches_read() {
+    let cow = SyncCow::new(vec![1, 2, 3]);
+    assert_eq!(cow.peek(|v| v.len()), cow.read().len());
+    cow.edit(|v| v.push(4));
+    assert_eq!(cow.peek(|v| v.len()), 4);
+    assert_eq!(cow.peek(|v| v.len()), cow.read().len());
+}
+
+#[test]
+fn send_sync() {
+    fn assert_send_sync<S: Send + Sync>() {}
+    assert_send_sync::<SyncCow<Vec<String>>>();
+    assert_send_sync::<SyncCow<Arc<Mutex<i32>>>>();
+}
+
+#[test]
+fn compare_and_set() {
+    let cow = Arc::new(SyncCow::new(0));
+    let successes = Arc::new(AtomicUsize::new(0));
+
+    let handles: Vec<_> = (1..=2)
+        .map(|i| {
+            let cow = cow.clone();
+            let successes = successes.clone();
+            std::thread::spawn(move || {
+                if cow.compare_and_set(&0, i).is_ok() {
+                    successes.fetch_add(1, Relaxed);
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(successes.load(Relaxed), 1);
+    let winner = *cow.read();
+    assert!(winner == 1 || winner == 2);
+    assert_eq!(*cow.compare_and_set(&0, 3).unwrap_err(), winner);
+}
+
+#[test]
+fn update() {
+    let cow = SyncCow::new(1);
+    cow.update(|x| x + 1);
+    assert_eq!(*cow.read(), 2);
+
+    // A minimal persistent list: pushing shares the existing nodes instead of copying them
+    #[derive(Clone)]
+    struct List(Option<Arc<(i32, List)>>);
+    impl List {
+        fn push(&self, value: i32) -> List {
+            List(Some(Arc::new((value, self.clone()))))
+        }
+    }
+
+    let cow = SyncCow::new(List(None).push(1));
+    let before = cow.read();
+    cow.update(|list| list.push(2));
+
+    let after = cow.read();
+    let (head, tail) = after.0.as_deref().unwrap();
+    assert_eq!(*head, 2);
+    assert!(Arc::ptr_eq(tail.0.as_ref().unwrap(), before.0.as_ref().unwrap()));
+}
+
+#[test]
+fn read_repeated_sees_one_snapshot() {
+    let cow = SyncCow::new(1);
+    let mut sum = 0;
+    let mut iters = 0;
+    cow.read_repeated(5, |x| {
+        sum += *x;
+        iters += 1;
+        assert_eq!(cow.active_readers(), 1, "the SyncCow is entered once");
+        if iters == 1 {
+            cow.set(10);
+        }
+    });
+    assert_eq!(sum, 5, "all iterations read the value from before the set");
+    assert_eq!(cow.active_readers(), 0);
+    assert_eq!(*cow.read(), 10);
+}
+
+#[test]
+fn borrow_holds_reader_until_dropped() {
+    let cow = SyncCow::new(vec![1, 2]);
+    let values = cow.borrow();
+    assert_eq!(*values, [1, 2]);
+    assert_eq!(format!("{:?}", values), "[1, 2]");
+    assert_eq!(cow.active_readers(), 1);
+    assert_eq!(cow.current_strong_count(), 0, "borrowing doesn't clone the Arc");
+
+    // The first edit publishes into the other buffer, so it doesn't wait for the borrow
+    cow.edit(|v| v.push(3));
+    assert_eq!(*values, [1, 2]);
+    drop(values);
+    assert_eq!(cow.active_readers(), 0);
+    assert_eq!(*cow.borrow(), [1, 2, 3]);
+    assert_eq!(cow.active_readers(), 0);
+}
+
+#[test]
+fn active_readers() {
+    let cow = Arc::new(SyncCow::new(5));
+    let peeking = Arc::new(std::sync::Barrier::new(4));
+    let release = Arc::new(std::sync::Barrier::new(4));
+
+    let handles: Vec<_> = (0..3)
+        .map(|_| {
+            let (cow, peeking, release) = (cow.clone(), peeking.clone(), release.clone());
+            std::thread::spawn(move || {
+                cow.peek(|_| {
+                    peeking.wait();
+                    release.wait();
+                })
+            })
+        })
+        .collect();
+
+    peeking.wait();
+    assert_eq!(cow.active_readers(), 3);
+    release.wait();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert_eq!(cow.active_readers(), 0);
+}
+
+#[test]
+fn memory_footprint_counts_distinct_values() {
+    let empty = SyncCow::new([0u64; 0]).memory_footprint();
+    let cow = SyncCow::new([0u64; 128]);
+    assert_eq!(cow.memory_footprint() - empty, 2 * 1024);
+    cow.edit(|values| values[0] = 1);
+    assert_eq!(cow.memory_footprint() - empty, 2 * 1024);
+
+    // Both buffers share the initial value, which is counted once, with one Arc's counts
+    let shared = SyncCow::from_arc(Arc::new([0u64; 128]));
+    let arc_counts = 2 * std::mem::size_of::<usize>();
+    assert_eq!(cow.memory_footprint() - shared.memory_footprint(), 1024 + arc_counts);
+}
+
+#[test]
+#[cfg(feature = "get-size")]
+fn memory_footprint_deep_includes_owned_memory() {
+    let cow = SyncCow::new(vec![0u64; 100]);
+    assert_eq!(cow.memory_footprint_deep() - cow.memory_footprint(), 2 * 800);
+}
+
+#[test]
+fn current_strong_count_counts_held_reads() {
+    let cow = SyncCow::new(5);
+    let held = [cow.read(), cow.read()];
+    assert_eq!(cow.current_strong_count(), 2);
+    cow.edit(|x| *x = 6);
+    assert_eq!(cow.current_strong_count(), 0);
+    drop(held);
+
+    let shared = SyncCow::from_arc(Arc::new(5));
+    assert_eq!(shared.current_strong_count(), 0);
+    let _held = shared.read();
+    assert_eq!(shared.current_strong_count(), 1);
+}
+
+#[test]
+fn panicking_reader_does_not_block_writers() {
+    let armed = Arc::new(AtomicBool::new(false));
+    let cow = SyncCow::new(PanicOnClone {
+        value: 1,
+        armed: armed.clone(),
+    });
+
+    armed.store(true, Relaxed);
+    let peek_clone = std::panic::AssertUnwindSafe(|| cow.peek(|x| x.clone()));
+    assert!(std::panic::catch_unwind(peek_clone).is_err());
+    assert_eq!(cow.active_readers(), 0);
+
+    // Both buffers are published into, which would spin forever on a leaked reader count
+    armed.store(false, Relaxed);
+    for value in 2..4 {
+        cow.edit(|x| x.value = value);
+    }
+    assert_eq!(cow.read().value, 3);
+}
+
+#[test]
+fn get_matches_read() {
+    let cow = SyncCow::new(5);
+    assert_eq!(cow.get(), *cow.read());
+    cow.edit(|x| *x = 6);
+    assert_eq!(cow.get(), 6);
+
+    let cow = SyncCow::new(String::from("moo"));
+    assert_eq!(cow.get(), *cow.read());
+    let mut val = cow.get();
+    val.push('o');
+    assert_eq!(*cow.read(), "moo", "get must return an independent clone");
+}
+
+#[test]
+fn read_weak_dangles_once_superseded() {
+    let cow = SyncCow::new(5);
+    let weak = cow.read_weak();
+    let strong = cow.read();
+
+    // Publishing into every other buffer reuses the buffer holding the weak's value
+    for value in 6..8 {
+        cow.edit(|x| *x = value);
+    }
+    assert_eq!(*weak.upgrade().unwrap(), 5, "Readers still hold the value");
+    drop(strong);
+    assert!(weak.upgrade().is_none());
+}
+
+#[test]
+fn panicking_edit_publishes_nothing() {
+    let token = Arc::new(());
+    let cow = SyncCow::new((1, token.clone()));
+    let references = Arc::strong_count(&token);
+
+    let panicking_edit = std::panic::AssertUnwindSafe(|| {
+        cow.edit(|x| {
+            x.0 = 2;
+            panic!("edit failed");
+        })
+    });
+    assert!(std::panic::catch_unwind(panicking_edit).is_err());
+    assert_eq!(cow.read().0, 1);
+    assert_eq!(cow.version(), 0);
+    assert_eq!(Arc::strong_count(&token), references, "The edited clone must be freed");
+
+    cow.edit(|x| x.0 = 3);
+    assert_eq!(cow.read().0, 3);
+    assert!(cow.try_edit(|x| x.0 = 4).is_ok());
+    assert_eq!(cow.read().0, 4);
+}
+
+#[test]
+fn new_with_calls_factory_twice() {
+    let calls = AtomicUsize::new(0);
+    let clones = Arc::new(AtomicUsize::new(0));
+    let _cow = SyncCow::new_with(|| {
+        calls.fetch_add(1, Relaxed);
+        CloneCounter {
+            clones: clones.clone(),
+        }
+    });
+    assert_eq!(calls.load(Relaxed), 2);
+    assert_eq!(clones.load(Relaxed), 0, "new_with must not clone the value");
+}
+
+#[test]
+fn swap_exchanges_values() {
+    let a = SyncCow::new(String::from("moo"));
+    let b = SyncCow::new(String::from("mooo"));
+    let (held_a, held_b) = (a.read(), b.read());
+
+    a.swap(&b);
+    assert!(Arc::ptr_eq(&a.read(), &held_b), "swap must not clone the values");
+    assert!(Arc::ptr_eq(&b.read(), &held_a), "swap must not clone the values");
+
+    a.swap(&a);
+    assert_eq!(*a.read(), "mooo");
+}
+
+#[test]
+fn swap_in_both_directions_does_not_deadlock() {
+    let a = Arc::new(SyncCow::new(1));
+    let b = Arc::new(SyncCow::new(2));
+
+    let handles: Vec<_> = [(a.clone(), b.clone()), (b.clone(), a.clone())]
+        .into_iter()
+        .map(|(x, y)| {
+            std::thread::spawn(move || {
+                for _ in 0..100 {
+                    x.swap(&y);
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    // 200 swaps restore the initial values
+    assert_eq!((*a.read(), *b.read()), (1, 2));
+}
+
+#[test]
+fn read_map_keeps_parent_alive() {
+    #[derive(Clone)]
+    struct User {
+        name: String,
+        age: u8,
+    }
+
+    let cow = SyncCow::new(User {
+        name: String::from("Moo"),
+        age: 3,
+    });
+    let name = cow.read_map(|user| user.name.as_str());
+    cow.set(User {
+        name: String::from("Mooo"),
+        age: 4,
+    });
+    cow.set(User {
+        name: String::from("Moooo"),
+        age: 5,
+    });
+
+    // Both buffers have been replaced, only the handle keeps the first value alive
+    assert_eq!(&*name, "Moo");
+    assert_eq!(MappedArc::parent(&name).age, 3);
+    assert_eq!(Arc::strong_count(MappedArc::parent(&name)), 1);
+    assert_eq!(&*name.clone(), "Moo");
+}
+
+#[test]
+fn map_read_wraps_derived_value() {
+    let cow = SyncCow::new(vec![1, 2, 3]);
+    let sum: Arc<i32> = cow.map_read(|v| v.iter().sum());
+    cow.edit(|v| v.push(4));
+    assert_eq!(*sum, 6);
+    assert_eq!(Arc::strong_count(&sum), 1);
+    assert_eq!(cow.current_strong_count(), 0, "the source value isn't held");
+    assert_eq!(*cow.map_read(|v| v.len()), 4);
+}
+
+#[test]
+fn read_into_reuses_allocation() {
+    let cow = SyncCow::new(vec![1u8; 512]);
+    let mut buf = Vec::with_capacity(1024);
+    buf.extend_from_slice(&[2u8; 1000]);
+    let allocation = buf.as_ptr();
+
+    cow.read_into(&mut buf);
+    assert_eq!(buf, *cow.read());
+    assert_eq!(buf.as_ptr(), allocation, "read_into must not reallocate");
+
+    cow.edit(|v| v.resize(1024, 3));
+    cow.read_into(&mut buf);
+    assert_eq!(buf, *cow.read());
+    assert_eq!(buf.as_ptr(), allocation, "read_into must not reallocate");
+}
+
+#[test]
+fn read_guard_refresh() {
+    let cow = SyncCow::new(vec![1]);
+    let mut guard = cow.read_guard();
+    assert_eq!(*guard, vec![1]);
+    assert_eq!(guard.len(), 1);
+
+    cow.edit(|v| v.push(2));
+    assert_eq!(*guard, vec![1], "Guard must keep its snapshot until refreshed");
+    guard.refresh();
+    assert_eq!(*guard, vec![1, 2]);
+    assert_eq!(format!("{:?}", guard), "[1, 2]");
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn metrics_count_edits() {
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    metrics::with_local_recorder(&recorder, || {
+        let cow = SyncCow::new(5);
+        cow.edit(|x| *x = 6);
+        cow.set(7);
+        assert_eq!(*cow.read(), 7);
+    });
+
+    let snapshot = snapshotter.snapshot().into_vec();
+    let counter = |name: &str| {
+        snapshot
+            .iter()
+            .find(|(key, ..)| key.key().name() == name)
+            .map(|(.., value)| value)
+    };
+    assert_eq!(counter("sync_cow.edits_total"), Some(&DebugValue::Counter(2)));
+    assert_eq!(counter("sync_cow.reads_total"), Some(&DebugValue::Counter(1)));
+}
+
+#[test]
+fn shared_clones_observe_edits() {
+    let cow = SyncCow::shared(vec![1]);
+    let other = cow.clone();
+    cow.edit(|v| v.push(2));
+    assert_eq!(*other.read(), vec![1, 2]);
+    other.edit(|v| v.push(3));
+    assert_eq!(*cow.read(), vec![1, 2, 3]);
+}
+
+#[test]
+fn equal_values_hash_equal() {
+    use std::hash::BuildHasher;
+
+    let hasher = std::collections::hash_map::RandomState::new();
+    let a = SyncCow::new(String::from("moo"));
+    let b = SyncCow::new(String::from("mo"));
+    b.edit(|s| s.push('o'));
+    assert_eq!(hasher.hash_one(&a), hasher.hash_one(&b));
+    assert_eq!(hasher.hash_one(&a), hasher.hash_one(String::from("moo")));
+}
+
+#[test]
+fn map_into_transforms_value() {
+    let cow = SyncCow::new(String::from("moo"));
+    cow.edit(|s| s.push('o'));
+    let len = cow.map_into(|s| s.len());
+    assert_eq!(*len.read(), 4);
+    assert_eq!(len.version(), 0);
+}
+
+#[test]
+fn reentrant_edit_panics() {
+    let cow = SyncCow::new(1);
+    let reentrant_edit = std::panic::AssertUnwindSafe(|| {
+        cow.edit(|x| {
+            assert_eq!(*cow.read(), 1, "Reads within edit_fn return the pre-edit value");
+            *x = 2;
+            cow.edit(|x| *x = 3);
+        })
+    });
+    let panic = std::panic::catch_unwind(reentrant_edit).unwrap_err();
+    let message = panic.downcast_ref::<&str>().unwrap();
+    assert!(message.starts_with("reentrant edit detected"), "{}", message);
+
+    assert_eq!(*cow.read(), 1);
+    cow.edit(|x| *x = 4);
+    assert_eq!(*cow.read(), 4);
+}
+
+#[test]
+fn reentrant_try_edits_return_error() {
+    let cow = SyncCow::new(1);
+    cow.edit(|x| {
+        assert_eq!(cow.try_edit(|x| *x = 3), Err(SyncCowError::Reentrant));
+        let timeout = std::time::Duration::from_millis(1);
+        assert_eq!(cow.try_edit_timeout(timeout, |x| *x = 3), Err(SyncCowError::Reentrant));
+        assert_eq!(cow.try_edit_bounded(1, |x| *x = 3), Err(SyncCowError::Reentrant));
+        *x = 2;
+    });
+    assert_eq!(*cow.read(), 2);
+    assert_eq!(
+        SyncCowError::Reentrant.to_string(),
+        "SyncCow is already being edited by this thread"
+    );
+}
+
+#[test]
+fn fetch_update_retries_on_conflict() {
+    let cow = SyncCow::shared(0);
+    let successes = Arc::new(AtomicUsize::new(0));
+
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let cow = cow.clone();
+            let successes = successes.clone();
+            std::thread::spawn(move || {
+                for _ in 0..100 {
+                    if cow.fetch_update(|x| Some(x + 1)).is_ok() {
+                        successes.fetch_add(1, Relaxed);
+                    }
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(*cow.read(), successes.load(Relaxed));
+    assert_eq!(successes.load(Relaxed), 400);
+}
+
+#[test]
+fn builder_with_spin_backoff() {
+    let cow = Arc::new(SyncCow::builder().buffers(3).backoff(Backoff::Spin).build(0));
+    let stopped = Arc::new(AtomicBool::new(false));
+    let reader = {
+        let (cow, stopped) = (cow.clone(), stopped.clone());
+        std::thread::spawn(move || {
+            let mut last = 0;
+            while !stopped.load(Relaxed) {
+                let value = *cow.read();
+                assert!(value >= last, "Read {} after {}", value, last);
+                last = value;
+            }
+        })
+    };
+
+    for _ in 0..100 {
+        cow.edit(|x| *x += 1);
+    }
+    stopped.store(true, Relaxed);
+    reader.join().unwrap();
+    assert_eq!(*cow.read(), 100);
+}
+
+#[test]
+fn exponential_backoff_waits_for_slow_readers() {
+    let cow = Arc::new(SyncCow::builder().backoff(Backoff::Exponential).build(0));
+    let stopped = Arc::new(AtomicBool::new(false));
+    let reader = {
+        let (cow, stopped) = (cow.clone(), stopped.clone());
+        std::thread::spawn(move || {
+            while !stopped.load(Relaxed) {
+                // Holding the value long enough makes the writer back off until it sleeps
+                cow.peek(|value| {
+                    let before = *value;
+                    std::thread::sleep(std::time::Duration::from_millis(1));
+                    assert_eq!(*value, before, "Value changed while being read");
+                });
+            }
+        })
+    };
+
+    for _ in 0..20 {
+        cow.edit(|x| *x += 1);
+    }
+    stopped.store(true, Relaxed);
+    reader.join().unwrap();
+    assert_eq!(*cow.read(), 20);
+}
+
+#[test]
+fn non_latest_buffer_holds_previous_value() {
+    let cow = SyncCow::new(1);
+    let (values, latest) = cow.debug_buffers();
+    assert_eq!((*values[0], *values[1], latest), (1, 1, 0));
+
+    cow.edit(|x| *x = 2);
+    let (values, latest) = cow.debug_buffers();
+    assert_eq!(latest, 1);
+    assert_eq!((*values[latest], *values[1 - latest]), (2, 1));
+
+    cow.edit(|x| *x = 3);
+    let (values, latest) = cow.debug_buffers();
+    assert_eq!(latest, 0);
+    assert_eq!((*values[latest], *values[1 - latest]), (3, 2));
+    assert_eq!(cow.active_readers(), 0);
+}
+
+#[test]
+fn publish_drains_readers_of_swapped_buffer() {
+    let cow = Arc::new(SyncCow::new(0));
+    let (entered_tx, entered_rx) = std::sync::mpsc::channel();
+    let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+    let reader = {
+        let cow = cow.clone();
+        std::thread::spawn(move || {
+            cow.peek(|value| {
+                entered_tx.send(()).unwrap();
+                release_rx.recv().unwrap();
+                assert_eq!(*value, 0);
+            })
+        })
+    };
+    entered_rx.recv().unwrap();
+    // The reader is counted in buffer 0, the latest one
+    assert_eq!((cow.buffers[0].readers(), cow.buffers[1].readers()), (1, 0));
+
+    // Publishing into buffer 1 doesn't wait for the reader of buffer 0
+    cow.edit(|x| *x = 1);
+    assert_eq!(cow.latest.load(Relaxed), 1);
+
+    // Publishing into buffer 0 swaps its pointer and waits for the reader counted there
+    let published = Arc::new(AtomicBool::new(false));
+    let writer = {
+        let (cow, published) = (cow.clone(), published.clone());
+        std::thread::spawn(move || {
+            cow.edit(|x| *x = 2);
+            published.store(true, Relaxed);
+        })
+    };
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    assert!(!published.load(Relaxed), "the writer must wait for the reader of buffer 0");
+    assert_eq!(*cow.read(), 1, "the pending value isn't latest yet");
+
+    release_tx.send(()).unwrap();
+    reader.join().unwrap();
+    writer.join().unwrap();
+    assert!(published.load(Relaxed));
+    assert_eq!(cow.latest.load(Relaxed), 0);
+    assert_eq!(*cow.read(), 2);
+}
+
+#[test]
+fn reset_waits_for_readers_of_all_buffers() {
+    let cow = SyncCow::new(1);
+    cow.reset(2);
+    assert_eq!(cow.active_readers(), 0);
+    assert_eq!(*cow.read(), 2);
+
+    let cow = Arc::new(SyncCow::new(vec![0]));
+    let (entered_tx, entered_rx) = std::sync::mpsc::channel();
+    let reader = {
+        let cow = cow.clone();
+        std::thread::spawn(move || {
+            cow.peek(|value| {
+                entered_tx.send(()).unwrap();
+                std::thread::sleep(std::time::Duration::from_millis(50));
+                value.len()
+            })
+        })
+    };
+    entered_rx.recv().unwrap();
+    // `set` would return right away, as it publishes into the buffer without readers
+    cow.reset(vec![]);
+    assert_eq!(cow.active_readers(), 0);
+    assert_eq!(reader.join().unwrap(), 1);
+}
+
+#[test]
+fn from_arc_supports_non_clone_values() {
+    struct NotClone(u32);
+
+    let initial = Arc::new(NotClone(1));
+    let cow = SyncCow::from_arc(initial.clone());
+    assert!(Arc::ptr_eq(&cow.read(), &initial));
+
+    cow.set(NotClone(2));
+    assert_eq!(cow.read().0, 2);
+    let previous = cow.replace(NotClone(3));
+    assert_eq!(previous.0, 2);
+    assert_eq!(cow.peek(|value| value.0), 3);
+}
+
+#[test]
+fn with_current_ptr_reads_current_value() {
+    #[derive(Clone)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    let cow = SyncCow::new(Point { x: 1, y: 2 });
+    cow.edit(|p| p.y = 3);
+    let y = unsafe { cow.with_current_ptr(|ptr| (*ptr).y) };
+    assert_eq!(y, cow.read().y);
+    assert_eq!(cow.read().x, 1);
+    assert_eq!(cow.active_readers(), 0);
+}
+
+#[test]
+fn reader_counts_return_to_zero_at_quiescence() {
+    let cow = Arc::new(SyncCow::new(0u64));
+    let readers: Vec<_> = (0..32)
+        .map(|i| {
+            let cow = cow.clone();
+            std::thread::spawn(move || {
+                for _ in 0..1000 {
+                    if i % 2 == 0 {
+                        std::hint::black_box(cow.read());
+                    } else {
+                        cow.peek(|x| std::hint::black_box(*x));
+                    }
+                }
+            })
+        })
+        .collect();
+
+    for _ in 0..100 {
+        cow.edit(|x| *x += 1);
+    }
+    for reader in readers {
+        reader.join().unwrap();
+    }
+    assert_eq!(cow.active_readers(), 0);
+    assert_eq!(*cow.read(), 100);
+}
+
+#[test]
+fn map_ext_edits_entries() {
+    use std::collections::{BTreeMap, HashMap};
+
+    let hash = SyncCow::new(HashMap::new());
+    let before = hash.read();
+    hash.edit_entry("moo", |n: &mut u32| *n += 1);
+    hash.edit_entry("moo", |n| *n += 1);
+    assert_eq!(hash.insert("mooo", 5), None);
+    assert_eq!(hash.insert("mooo", 6), Some(5));
+    assert_eq!(*hash.read(), HashMap::from([("moo", 2), ("mooo", 6)]));
+    assert_eq!(hash.remove(&"moo"), Some(2));
+    let version = hash.version();
+    assert_eq!(hash.remove(&"moo"), None);
+    assert_eq!(hash.version(), version, "removing a missing key must not publish");
+    assert!(before.is_empty(), "edits must not change already read maps");
+
+    let btree = SyncCow::new(BTreeMap::new());
+    let len = btree.edit_entry(1, |v: &mut Vec<u8>| {
+        v.push(1);
+        v.len()
+    });
+    assert_eq!(len, 1);
+    assert_eq!(btree.insert(2, vec![2]), None);
+    assert_eq!(*btree.read(), BTreeMap::from([(1, vec![1]), (2, vec![2])]));
+    let version = btree.version();
+    assert_eq!(btree.remove(&3), None);
+    assert_eq!(btree.version(), version);
+}
+
+#[test]
+fn wait_for_version_blocks_until_published() {
+    let cow = Arc::new(SyncCow::new(0));
+    let writer = {
+        let cow = cow.clone();
+        std::thread::spawn(move || {
+            for _ in 0..5 {
+                std::thread::sleep(std::time::Duration::from_millis(2));
+                cow.edit(|x| *x += 1);
+            }
+        })
+    };
+
+    assert_eq!(*cow.wait_for_version(5), 5);
+    assert_eq!(*cow.wait_for_version(3), 5, "reached versions must return immediately");
+    writer.join().unwrap();
+}
+
+#[test]
+fn changes_yields_published_values() {
+    let cow = Arc::new(SyncCow::new(0));
+    let changes = cow.changes();
+    let writer = {
+        let cow = cow.clone();
+        std::thread::spawn(move || {
+            for _ in 0..5 {
+                cow.edit(|x| *x += 1);
+            }
+        })
+    };
+
+    // Intermediate values may be coalesced, but they arrive in order and end with the last one
+    let mut seen = Vec::new();
+    for value in changes {
+        seen.push(*value);
+        if *value == 5 {
+            break;
+        }
+    }
+    assert!(seen.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", seen);
+    writer.join().unwrap();
+}
+
+#[test]
+fn drop_after_scoped_readers() {
+    let clones = Arc::new(AtomicUsize::new(0));
+    let cow = SyncCow::new(CloneCounter {
+        clones: clones.clone(),
+    });
+
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                for _ in 0..1000 {
+                    cow.peek(|value| assert!(Arc::ptr_eq(&value.clones, &clones)));
+                }
+            });
+        }
+        cow.edit(|_| ());
+    });
+    // The scope joined all readers, so they can't be mid-read while the cow is dropped
+    drop(cow);
+    assert_eq!(Arc::strong_count(&clones), 1, "dropping must free all buffers");
+}
+
+#[test]
+fn drop_with_leaked_borrow() {
+    let clones = Arc::new(AtomicUsize::new(0));
+    let cow = SyncCow::new(CloneCounter {
+        clones: clones.clone(),
+    });
+    std::mem::forget(cow.borrow());
+    assert_eq!(cow.active_readers(), 1);
+    drop(cow);
+    assert_eq!(Arc::strong_count(&clones), 1, "dropping must free all buffers");
+}
+
+#[test]
+fn try_into_inner_fails_while_read() {
+    struct NotClone(u32);
+
+    let cow = SyncCow::from_arc(Arc::new(NotClone(1)));
+    cow.set(NotClone(2));
+    let held = cow.read();
+    let cow = match cow.try_into_inner() {
+        Ok(_) => panic!("try_into_inner must fail while a read Arc is alive"),
+        Err(cow) => cow,
+    };
+    assert_eq!(cow.read().0, 2);
+    assert_eq!(cow.version(), 1);
+
+    drop(held);
+    assert_eq!(cow.try_into_inner().ok().map(|value| value.0), Some(2));
+
+    // Both buffers share the initial Arc, which must not count as an outstanding reader
+    let cow = SyncCow::from_arc(Arc::new(NotClone(3)));
+    assert_eq!(cow.try_into_inner().ok().map(|value| value.0), Some(3));
+}
+
+#[test]
+fn read_with_freshness_detects_concurrent_writes() {
+    let cow = Arc::new(SyncCow::new(0u64));
+    for _ in 0..100 {
+        assert!(cow.read_with_freshness().1, "reads without writers are always fresh");
+    }
+
+    let stopped = Arc::new(AtomicBool::new(false));
+    let writer = {
+        let (cow, stopped) = (cow.clone(), stopped.clone());
+        std::thread::spawn(move || {
+            while !stopped.load(Relaxed) {
+                cow.edit(|x| *x += 1);
+            }
+        })
+    };
+
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while cow.read_with_freshness().1 {
+        assert!(std::time::Instant::now() < deadline, "no stale read observed");
+    }
+    stopped.store(true, Relaxed);
+    writer.join().unwrap();
+}
+
+#[test]
+fn try_read_succeeds_without_writers() {
+    let cow = SyncCow::new(5);
+    for _ in 0..100 {
+        assert_eq!(cow.try_read().as_deref(), Some(&5));
+    }
+    cow.edit(|x| *x = 6);
+    assert_eq!(cow.try_read().as_deref(), Some(&6));
+}
+
+#[test]
+fn new_shared_initial_does_not_clone() {
+    let clones = Arc::new(AtomicUsize::new(0));
+    let cow = SyncCow::new_shared_initial(CloneCounter {
+        clones: clones.clone(),
+    });
+    assert_eq!(clones.load(Relaxed), 0, "construction must not clone the value");
+    let (values, _) = cow.debug_buffers();
+    assert!(Arc::ptr_eq(&values[0], &values[1]));
+
+    cow.edit(|_| ());
+    cow.edit(|_| ());
+    assert_eq!(clones.load(Relaxed), 2, "each edit clones exactly once");
+}
+
+#[test]
+fn edits_after_shared_initial_arc() {
+    let cow = SyncCow::new_shared_initial(vec![1]);
+    let initial = cow.read();
+    cow.edit(|v| v.push(2));
+    assert!(cow.edit_if(|v| {
+        v.push(3);
+        true
+    }));
+    assert_eq!(*cow.read(), vec![1, 2, 3]);
+    assert_eq!(*initial, vec![1], "edits must not mutate the shared initial value");
+}
+
+#[test]
+fn with_cloner_edits_non_clone_values() {
+    struct NotClone(u32);
+
+    let cow = SyncCow::with_cloner(NotClone(1), |v| NotClone(v.0));
+    cow.edit(|v| v.0 += 1);
+    assert!(cow.edit_if(|v| {
+        v.0 += 1;
+        true
+    }));
+    cow.edit_guard().0 += 1;
+    let other = SyncCow::with_cloner(NotClone(10), |v| NotClone(v.0));
+    crate::edit_pair(&cow, &other, |a, b| {
+        a.0 += 1;
+        b.0 += 1;
+    });
+    assert_eq!(cow.read().0, 5);
+    assert_eq!(other.read().0, 11);
+}
+
+#[test]
+#[should_panic(expected = "no cloner")]
+fn edit_without_cloner_panics() {
+    struct NotClone(u32);
+
+    let cow = SyncCow::from_arc(Arc::new(NotClone(1)));
+    cow.edit(|v| v.0 += 1);
+}
+
+#[test]
+fn snapshot_into_owned() {
+    let clones = Arc::new(AtomicUsize::new(0));
+    let cow = SyncCow::new(CloneCounter {
+        clones: clones.clone(),
+    });
+    let before = clones.load(Relaxed);
+
+    // The SyncCow still holds the value, so taking ownership clones it
+    let _owned = cow.snapshot().into_owned();
+    assert_eq!(clones.load(Relaxed), before + 1);
+
+    // After two edits both buffers hold newer values and the snapshot is unique
+    let snapshot = cow.snapshot();
+    cow.edit(|_| ());
+    cow.edit(|_| ());
+    let before = clones.load(Relaxed);
+    let _owned = snapshot.into_owned();
+    assert_eq!(clones.load(Relaxed), before, "unique snapshots must be unwrapped");
+
+    let owned = Snapshot::from(vec![1]);
+    assert_eq!(owned.as_ref(), &vec![1]);
+    assert_eq!(owned.into_owned(), vec![1]);
+}
+
+#[test]
+fn read_previous_returns_replaced_value() {
+    let cow = SyncCow::new("initial");
+    assert_eq!(*cow.read_previous(), "initial");
+    cow.set("a");
+    cow.set("b");
+    assert_eq!(*cow.read(), "b");
+    assert_eq!(*cow.read_previous(), "a");
+
+    // With more buffers, the previous value's buffer isn't the only candidate for the next one
+    let cow = SyncCow::with_buffers(0, 4);
+    for i in 1..10 {
+        cow.set(i);
+        assert_eq!((*cow.read(), *cow.read_previous()), (i, i - 1));
+    }
+    assert_eq!(cow.active_readers(), 0);
+}
+
+#[test]
+fn rollback_restores_previous_value() {
+    let cow = SyncCow::new(String::from("a"));
+    cow.set(String::from("b"));
+    let rolled_back = cow.read();
+
+    cow.rollback();
+    assert_eq!(*cow.read(), "a");
+    assert_eq!(*cow.read_previous(), "a");
+    assert_eq!(cow.version(), 2);
+    assert_eq!(Arc::strong_count(&rolled_back), 1, "rollback must release the rolled back value");
+
+    cow.rollback();
+    assert_eq!(*cow.read(), "a");
+    cow.set(String::from("c"));
+    assert_eq!((cow.read().as_str(), cow.read_previous().as_str()), ("c", "a"));
+}
+
+#[test]
+fn try_edit_bounded_times_out_on_stuck_reader() {
+    let cow = Arc::new(SyncCow::new(0));
+    let peeking = Arc::new(std::sync::Barrier::new(2));
+    let release = Arc::new(std::sync::Barrier::new(2));
+    let reader = {
+        let (cow, peeking, release) = (cow.clone(), peeking.clone(), release.clone());
+        std::thread::spawn(move || {
+            cow.peek(|_| {
+                peeking.wait();
+                release.wait();
+            })
+        })
+    };
+    peeking.wait();
+
+    // The first edit publishes into the free buffer, the next one would have to wait for the
+    // reader stuck in the buffer it left behind
+    assert_eq!(cow.try_edit_bounded(10, |x| *x = 1), Ok(()));
+    assert_eq!(
+        cow.try_edit_bounded(10, |_| panic!("must not edit")),
+        Err(SyncCowError::DrainTimeout)
+    );
+    assert_eq!((*cow.read(), cow.version()), (1, 1));
+
+    release.wait();
+    reader.join().unwrap();
+    assert_eq!(cow.try_edit_bounded(10, |x| *x = 2), Ok(()));
+    assert_eq!(*cow.read(), 2);
+}
+
+#[test]
+fn with_computes_from_contents() {
+    let cow = SyncCow::new(vec![String::from("moo"), String::from("mooo")]);
+    let total = cow.with(|words| words.iter().map(String::len).sum::<usize>());
+    assert_eq!(total, 7);
+    assert_eq!(cow.with(|words| words.len()), cow.read().len());
+    assert_eq!(cow.active_readers(), 0);
+}
+
+#[test]
+#[cfg(feature = "parking_lot")]
+fn parking_lot_write_lock_survives_panicking_edit() {
+    let cow = Arc::new(SyncCow::new(1));
+    let panicking = {
+        let cow = cow.clone();
+        std::thread::spawn(move || cow.edit(|_| panic!("edit failed")))
+    };
+    assert!(panicking.join().is_err());
+
+    cow.edit(|x| *x = 2);
+    assert_eq!(cow.try_edit(|x| *x += 1), Ok(()));
+    assert_eq!((*cow.read(), cow.version()), (3, 2));
+}
+
+#[test]
+fn snapshot_fields_are_consistent() {
+    let cow = Arc::new(SyncCow::new((0u64, 0u64)));
+    let writer = {
+        let cow = cow.clone();
+        std::thread::spawn(move || {
+            for _ in 0..500 {
+                cow.edit(|(a, b)| {
+                    *a += 1;
+                    *b += 1;
+                });
+            }
+        })
+    };
+
+    for _ in 0..500 {
+        let snapshot = cow.snapshot();
+        assert_eq!(snapshot.0, snapshot.1, "fields of one snapshot must match");
+    }
+    writer.join().unwrap();
+    assert_eq!(*cow.read(), (500, 500));
+}
+
+#[test]
+fn compare_exchange_retry_loop() {
+    let cow = Arc::new(SyncCow::new(0));
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let cow = cow.clone();
+            std::thread::spawn(move || {
+                for _ in 0..50 {
+                    let (mut version, mut value) = (cow.version(), cow.read());
+                    while let Err(current) = cow.compare_exchange(version, *value + 1) {
+                        (version, value) = current;
+                    }
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert_eq!((*cow.read(), cow.version()), (200, 200));
+}
+
+#[test]
+#[cfg(feature = "profiling")]
+fn clone_observer_times_slow_clones() {
+    struct SlowClone;
+
+    impl Clone for SlowClone {
+        fn clone(&self) -> Self {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            SlowClone
+        }
+    }
+
+    let cow = SyncCow::new_shared_initial(SlowClone);
+    let durations = Arc::new(Mutex::new(Vec::new()));
+    {
+        let durations = durations.clone();
+        cow.set_clone_observer(move |duration| durations.lock().unwrap().push(duration));
+    }
+    cow.edit(|_| ());
+    cow.set(SlowClone);
+    assert!(!cow.edit_if(|_| false));
+
+    let durations = durations.lock().unwrap();
+    assert_eq!(durations.len(), 2, "only edits clone the value");
+    let slow = std::time::Duration::from_millis(5);
+    assert!(durations.iter().all(|duration| *duration >= slow), "{:?}", durations);
+}
+
+#[test]
+fn edit_dedup_skips_unchanged_values() {
+    let cow = SyncCow::new(vec![1, 2]);
+    let before = cow.read();
+    assert!(!cow.edit_dedup(|v| v[1] = 2));
+    assert_eq!(cow.version(), 0);
+    assert!(Arc::ptr_eq(&cow.read(), &before), "readers must keep the current value");
+
+    assert!(cow.edit_dedup(|v| v.push(3)));
+    assert_eq!((cow.read().as_slice(), cow.version()), (&[1, 2, 3][..], 1));
+}
+
+#[test]
+fn with_cloner_replaces_clone_in_edits() {
+    #[derive(Clone, Debug, PartialEq)]
+    struct Entry {
+        value: u32,
+        touched: Option<std::time::Instant>,
+    }
+
+    let cow = SyncCow::with_cloner(Entry { value: 1, touched: None }, |entry: &Entry| Entry {
+        value: entry.value,
+        touched: None,
+    });
+    cow.edit(|entry| {
+        entry.value = 2;
+        entry.touched = Some(std::time::Instant::now());
+    });
+    assert!(cow.read().touched.is_some());
+
+    // The next edit starts from the cloner's copy with the timestamp reset
+    cow.edit(|entry| {
+        assert_eq!(entry.touched, None);
+        entry.value += 1;
+    });
+    assert_eq!(*cow.read(), Entry { value: 3, touched: None });
+}
+
+#[test]
+#[cfg(feature = "arc-swap")]
+fn arc_swap_round_trip() {
+    let cow = SyncCow::new(vec![1, 2]);
+    cow.edit(|v| v.push(3));
+    let swap = cow.to_arc_swap();
+    assert_eq!(**swap.load(), [1, 2, 3]);
+
+    swap.rcu(|v| {
+        let mut v = Vec::clone(v);
+        v.push(4);
+        v
+    });
+    let cow = SyncCow::from_arc_swap(&swap);
+    assert_eq!(*cow.read(), [1, 2, 3, 4]);
+    cow.edit(|v| v.push(5));
+    assert_eq!(**swap.load(), [1, 2, 3, 4]);
+    assert_eq!(cow.into_inner(), [1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn compact_shrinks_all_buffers() {
+    let cow = SyncCow::new(vec![0; 10_000]);
+    // Truncating an edit's clone keeps its capacity, and the previous value keeps it too
+    cow.edit(|v| v.truncate(10));
+    cow.edit(|v| v.truncate(5));
+    assert!(cow.read_previous().capacity() >= 10_000);
+    assert!(cow.read().capacity() >= 10);
+
+    cow.compact();
+    let (values, _) = cow.debug_buffers();
+    assert!(values.iter().all(|v| v.capacity() == 5));
+    assert_eq!(*cow.read(), [0; 5]);
+    assert_eq!(cow.version(), 4);
+}
+
+#[test]
+fn compact_shrinks_buffers_with_readers() {
+    let cow = SyncCow::with_buffers(vec![0; 10_000], 3);
+    cow.edit(|v| v.truncate(5));
+    let borrowed = std::sync::Barrier::new(2);
+    std::thread::scope(|s| {
+        s.spawn(|| {
+            // Keeps the latest buffer read while compact publishes into the other ones
+            let _values = cow.borrow();
+            borrowed.wait();
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        });
+        borrowed.wait();
+        cow.compact();
+    });
+    let (values, _) = cow.debug_buffers();
+    assert!(values.iter().all(|v| v.capacity() == 5));
+}
+
+#[test]
+fn sync_cow_option_conveniences() {
+    let cow: SyncCowOption<Vec<i32>> = SyncCow::new(None);
+    assert_eq!(cow.read_or(vec![0]), [0]);
+    cow.set_some(vec![1, 2]);
+    assert_eq!(cow.read_or(vec![0]), [1, 2]);
+    cow.clear();
+    assert!(cow.read().is_none());
+    cow.set_some(vec![3]);
+    assert_eq!(*cow.take(), Some(vec![3]));
+    assert!(cow.read_or(vec![]).is_empty());
+    assert_eq!(cow.version(), 4);
+}
+
+#[test]
+fn len_ext_delegates_to_value() {
+    use std::collections::HashMap;
+
+    let list = SyncCow::new(vec![1, 2]);
+    assert_eq!(list.len(), 2);
+    assert!(!list.is_empty());
+    list.edit(|v| v.clear());
+    assert!(list.is_empty());
+
+    let map = SyncCow::new(HashMap::new());
+    assert!(map.is_empty());
+    map.edit(|m| m.insert("moo", 1));
+    assert_eq!(map.len(), 1);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn soak_reads_are_published_and_monotonic() {
+    // Each published value carries a checksum, so a value that was never published, e.g. a
+    // half-written or freed one, is detected
+    #[derive(Clone)]
+    struct Published {
+        seq: u64,
+        check: u64,
+    }
+
+    fn check(seq: u64) -> u64 {
+        seq.wrapping_mul(0x9e37_79b9_7f4a_7c15)
+    }
+
+    let cow = Arc::new(SyncCow::new(Published { seq: 0, check: check(0) }));
+    let deadline = std::time::Instant::now() + std::time::Duration::from_millis(500);
+
+    let writers: Vec<_> = (0..4)
+        .map(|_| {
+            let cow = cow.clone();
+            std::thread::spawn(move || {
+                while std::time::Instant::now() < deadline {
+                    cow.edit(|value| {
+                        value.seq += 1;
+                        value.check = check(value.seq);
+                    });
+                }
+            })
+        })
+        .collect();
+
+    let readers: Vec<_> = (0..4)
+        .map(|reader| {
+            let cow = cow.clone();
+            std::thread::spawn(move || {
+                let mut last = 0;
+                let mut borrow = false;
+                while std::time::Instant::now() < deadline {
+                    // Alternate between the Arc-cloning and the borrowing read path
+                    borrow = !borrow;
+                    let seq = if !borrow {
+                        let value = cow.read();
+                        assert_eq!(value.check, check(value.seq), "unpublished value read");
+                        value.seq
+                    } else {
+                        cow.peek(|value| {
+                            assert_eq!(value.check, check(value.seq), "unpublished value read");
+                            value.seq
+                        })
+                    };
+                    assert!(seq >= last, "reader {} read {} after {}", reader, seq, last);
+                    last = seq;
+                }
+                last
+            })
+        })
+        .collect();
+
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    let published = cow.read().seq;
+    assert_eq!(published, cow.version());
+    for reader in readers {
+        assert!(reader.join().unwrap() <= published);
+    }
+}
+
+#[test]
+#[cfg(feature = "allocator_api")]
+fn new_in_balances_allocations() {
+    use std::alloc::{AllocError, Allocator, Global, Layout};
+    use std::ptr::NonNull;
+
+    /// Counts the allocations and deallocations made through it
+    #[derive(Clone, Default)]
+    struct CountingAllocator {
+        allocs: Arc<AtomicUsize>,
+        deallocs: Arc<AtomicUsize>,
+    }
+
+    unsafe impl Allocator for CountingAllocator {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            self.allocs.fetch_add(1, Relaxed);
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            self.deallocs.fetch_add(1, Relaxed);
+            unsafe { Global.deallocate(ptr, layout) }
+        }
+    }
+
+    let counting = CountingAllocator::default();
+    let cow = SyncCow::new_in(vec![1], counting.clone());
+    assert_eq!(counting.allocs.load(Relaxed), 2, "one Box per buffer");
+    for i in 2..=10 {
+        cow.edit(|v| v.push(i));
+    }
+    cow.rollback();
+    let cloned = cow.clone();
+    cloned.edit(|v| v.clear());
+    assert_eq!(counting.allocs.load(Relaxed), 2 + 9 + 1 + 2 + 1);
+    assert_eq!(counting.deallocs.load(Relaxed), 9 + 1 + 1);
+
+    assert_eq!(*cow.read(), (1..=9).collect::<Vec<_>>());
+    drop(cow);
+    drop(cloned);
+    assert_eq!(counting.allocs.load(Relaxed), counting.deallocs.load(Relaxed));
+}
@@ -0,0 +1,52 @@
+//! Bridge to `tokio::sync::watch`, see [`SyncCow::watch`](crate::SyncCow::watch)
+
+use crate::SyncCow;
+use alloc::sync::Arc;
+use tokio::sync::watch;
+
+impl<T: Send + Sync + 'static> SyncCow<T> {
+    /// Subscribe to published values through a `tokio::sync::watch` channel.
+    ///
+    /// The receiver starts out with the current value marked as seen, and each publish sends
+    /// the new value into the channel, so async consumers can `changed().await` instead of
+    /// polling. Only publishes are sent, i.e. the changes that also reach
+    /// [`on_change`](SyncCow::on_change) callbacks: mutations through
+    /// [`get_mut`](SyncCow::get_mut) are not. The first call registers a forwarding callback with
+    /// [`on_change`](SyncCow::on_change), later calls subscribe to the same channel.
+    ///
+    /// Like any `watch` channel, changes are coalesced: a receiver only sees the latest value
+    /// when it checks, values published in between are missed.
+    ///
+    /// The channel keeps an Arc of the current value, like a held [`read`](SyncCow::read). Once
+    /// it exists, [`try_into_inner`](SyncCow::try_into_inner) fails, and
+    /// [`into_inner`](SyncCow::into_inner) clones the value while receivers are alive.
+    ///
+    /// Must not be called from within an edit or `on_change` callback of the same SyncCow, as
+    /// registering the callback takes the write-lock. Only available with the `async` feature.
+    ///
+    /// ```
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let cow = sync_cow::SyncCow::new(5);
+    /// let mut values = cow.watch();
+    /// cow.edit(|x| *x = 6);
+    /// values.changed().await.unwrap();
+    /// assert_eq!(**values.borrow_and_update(), 6);
+    /// # }
+    /// ```
+    pub fn watch(&self) -> watch::Receiver<Arc<T>> {
+        self.watch
+            .get_or_init(|| {
+                // Holding the write-lock, no value can be published between reading the
+                // initial value and registering the callback
+                let _lck = self.write_lock.lock();
+                let (sender, _) = watch::channel(self.read());
+                let forward = sender.clone();
+                self.on_change(move |value| {
+                    forward.send_replace(value.clone());
+                });
+                sender
+            })
+            .subscribe()
+    }
+}
@@ -0,0 +1,18 @@
+#![no_std]
+
+use sync_cow::SyncCow;
+
+pub fn edit_and_read(cow: &SyncCow<u32>) -> u32 {
+    cow.edit(|x| *x += 1);
+    cow.set(*cow.read() + 1);
+    let _ = cow.try_edit(|x| *x += 1);
+    *cow.read()
+}
+
+pub fn version(cow: &SyncCow<u32>) -> u64 {
+    cow.version()
+}
+
+pub fn new_cow() -> SyncCow<u32> {
+    SyncCow::new(5)
+}